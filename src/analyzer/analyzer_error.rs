@@ -0,0 +1,147 @@
+use crate::parser::source_position::{ SourceMap, Span };
+use crate::parser::syntax_error::snippet;
+
+/* Errors found while analyzing forms, every error carries the span of the offending form */
+#[derive(Clone, PartialEq, Debug)]
+pub enum AnalyzerError {
+    ExpectingUnit(Span),
+    ExpectingKeyValue(Span),
+    InvalidSyntax(Span),
+    UnexpandedConditional(Span),
+    UnexpectedNode(Span),
+
+    WrongNumberOfArgs(Span, Box<String>),
+    TooFewArguments(Span, Box<String>),
+    TooManyArguments(Span, Box<String>),
+    DefOutsideNamespace(Span),
+    DefNameNotSymbol(Span),
+    VarNotSymbol(Span),
+    AssignToLocal(Span, Box<String>),
+    MissingClassName(Span),
+
+    BindingsNotVector(Span, Box<String>),
+    OddBindingForms(Span, Box<String>),
+    BindingNotSymbol(Span, Box<String>),
+    RecurAcrossTry(Span),
+    RecurOutsideLoop(Span),
+    RecurNotInTail(Span),
+    RecurArityMismatch(Span, usize, usize),
+
+    MissingParameterVector(Span),
+    InvalidFnMethod(Span),
+    ParametersNotVector(Span),
+    InvalidRestParameter(Span),
+    UnsupportedBindingForm(Span, Box<String>),
+
+    InvalidTryClause(Span),
+    InvalidCatch(Span),
+    FinallyNotLast(Span),
+    MalformedMemberExpression(Span),
+    MemberNotSymbol(Span)
+}
+
+impl AnalyzerError {
+    pub fn span(&self) -> Span {
+        match self {
+            AnalyzerError::ExpectingUnit(span) |
+            AnalyzerError::ExpectingKeyValue(span) |
+            AnalyzerError::InvalidSyntax(span) |
+            AnalyzerError::UnexpandedConditional(span) |
+            AnalyzerError::UnexpectedNode(span) |
+            AnalyzerError::WrongNumberOfArgs(span, _) |
+            AnalyzerError::TooFewArguments(span, _) |
+            AnalyzerError::TooManyArguments(span, _) |
+            AnalyzerError::DefOutsideNamespace(span) |
+            AnalyzerError::DefNameNotSymbol(span) |
+            AnalyzerError::VarNotSymbol(span) |
+            AnalyzerError::AssignToLocal(span, _) |
+            AnalyzerError::MissingClassName(span) |
+            AnalyzerError::BindingsNotVector(span, _) |
+            AnalyzerError::OddBindingForms(span, _) |
+            AnalyzerError::BindingNotSymbol(span, _) |
+            AnalyzerError::RecurAcrossTry(span) |
+            AnalyzerError::RecurOutsideLoop(span) |
+            AnalyzerError::RecurNotInTail(span) |
+            AnalyzerError::RecurArityMismatch(span, _, _) |
+            AnalyzerError::MissingParameterVector(span) |
+            AnalyzerError::InvalidFnMethod(span) |
+            AnalyzerError::ParametersNotVector(span) |
+            AnalyzerError::InvalidRestParameter(span) |
+            AnalyzerError::UnsupportedBindingForm(span, _) |
+            AnalyzerError::InvalidTryClause(span) |
+            AnalyzerError::InvalidCatch(span) |
+            AnalyzerError::FinallyNotLast(span) |
+            AnalyzerError::MalformedMemberExpression(span) |
+            AnalyzerError::MemberNotSymbol(span) => *span
+        }
+    }
+
+    /* Formats the error with the offending source line, the same way as SyntaxError::render */
+    pub fn render(&self, source: &str, files: &SourceMap) -> String {
+        format!("error: {}\n{}", self, snippet(self.span(), source, files))
+    }
+}
+
+impl std::fmt::Display for AnalyzerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnalyzerError::ExpectingUnit(_) => write!(f, "Expecting unit to analyze!"),
+            AnalyzerError::ExpectingKeyValue(_) => write!(f, "Expecting key value pair in map!"),
+            AnalyzerError::InvalidSyntax(_) => write!(f, "Syntax error in expression!"),
+            AnalyzerError::UnexpandedConditional(_) => write!(f, "Reader conditionals must be expanded before analysis!"),
+            AnalyzerError::UnexpectedNode(_) => write!(f, "Unexpected node in expression!"),
+            AnalyzerError::WrongNumberOfArgs(_, form) => write!(f, "Wrong number of args passed to {}!", form),
+            AnalyzerError::TooFewArguments(_, form) => write!(f, "Too few arguments to {}!", form),
+            AnalyzerError::TooManyArguments(_, form) => write!(f, "Too many arguments to {}!", form),
+            AnalyzerError::DefOutsideNamespace(_) => write!(f, "Can't create defs outside of current ns!"),
+            AnalyzerError::DefNameNotSymbol(_) => write!(f, "First argument to def must be a symbol!"),
+            AnalyzerError::VarNotSymbol(_) => write!(f, "Expecting a single symbol in var!"),
+            AnalyzerError::AssignToLocal(_, name) => write!(f, "Cannot assign to non-mutable: {}", name),
+            AnalyzerError::MissingClassName(_) => write!(f, "Expecting class name in new!"),
+            AnalyzerError::BindingsNotVector(_, form) => write!(f, "Bad binding form, expected vector in {}!", form),
+            AnalyzerError::OddBindingForms(_, form) => write!(f, "Bad binding form, expected matched symbol expression pairs in {}!", form),
+            AnalyzerError::BindingNotSymbol(_, text) => write!(f, "Bad binding form, expected symbol but got: {}", text),
+            AnalyzerError::RecurAcrossTry(_) => write!(f, "Cannot recur across try!"),
+            AnalyzerError::RecurOutsideLoop(_) => write!(f, "Can only recur from inside loop or fn!"),
+            AnalyzerError::RecurNotInTail(_) => write!(f, "Can only recur from tail position!"),
+            AnalyzerError::RecurArityMismatch(_, expected, found) => {
+                write!(f, "Mismatched argument count to recur, expected: {} args, got: {}", expected, found)
+            },
+            AnalyzerError::MissingParameterVector(_) => write!(f, "Missing parameter vector in fn*!"),
+            AnalyzerError::InvalidFnMethod(_) => write!(f, "Expecting parameter vector or list of methods in fn*!"),
+            AnalyzerError::ParametersNotVector(_) => write!(f, "Expecting parameter vector in fn* method!"),
+            AnalyzerError::InvalidRestParameter(_) => write!(f, "Invalid parameter list, expecting single symbol after &!"),
+            AnalyzerError::UnsupportedBindingForm(_, text) => write!(f, "Unsupported binding form: {}", text),
+            AnalyzerError::InvalidTryClause(_) => write!(f, "Only catch or finally clause can follow catch in try expression!"),
+            AnalyzerError::InvalidCatch(_) => write!(f, "Expecting class and local name in catch!"),
+            AnalyzerError::FinallyNotLast(_) => write!(f, "Finally clause must be last in try expression!"),
+            AnalyzerError::MalformedMemberExpression(_) => write!(f, "Malformed member expression, expecting (. target member ...)"),
+            AnalyzerError::MemberNotSymbol(_) => write!(f, "Malformed member expression, expecting symbol as member!")
+        }
+    }
+}
+
+impl std::error::Error for AnalyzerError {}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::source_position::{ Position, SourceMap, Span };
+
+    use super::AnalyzerError;
+
+    #[test]
+    fn render_with_location() {
+        let mut files = SourceMap::new();
+        let core = files.add_file("core.clj");
+        let source = "(def x 1)\n(if a)\n";
+        let error = AnalyzerError::TooFewArguments(Span::new(Position::new(core, 2, 1, 10), Position::new(core, 2, 7, 16)), Box::new("if".to_string()));
+
+        assert_eq!(error.render(source, &files), "error: Too few arguments to if!\n --> core.clj:2:1\n  |\n2 | (if a)\n  | ^^^^^^")
+    }
+}
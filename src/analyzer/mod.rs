@@ -0,0 +1,2 @@
+pub mod analyzer_error;
+pub mod semantic_analyzer;
\ No newline at end of file
@@ -0,0 +1,647 @@
+use super::analyzer_error::AnalyzerError;
+use crate::parser::expression_parser::Node;
+use crate::parser::source_position::Span;
+
+/* The special forms of Clojure, everything else in operator position is an ordinary invocation */
+pub const SPECIAL_FORMS: [&str; 14] = [
+    "def", "if", "do", "let*", "fn*", "loop*", "recur", "quote", "var", "throw", "try", "set!", "new", "."
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    Constant(Node),
+    Local(Box<String>),
+    Var(Option<Box<String>>, Box<String>),
+    Vector(Vec<Expr>),
+    Map(Vec<(Expr, Expr)>),
+    Set(Vec<Expr>),
+    Def(Box<String>, Option<Box<Expr>>),
+    If(Box<Expr>, Box<Expr>, Option<Box<Expr>>),
+    Do(Vec<Expr>),
+    Let(Vec<(Box<String>, Expr)>, Box<Expr>),
+    Loop(Vec<(Box<String>, Expr)>, Box<Expr>),
+    Recur(Vec<Expr>),
+    Fn(Option<Box<String>>, Vec<Expr>),
+    FnMethod(Vec<Box<String>>, Option<Box<String>>, Box<Expr>),
+    Quote(Node),
+    TheVar(Option<Box<String>>, Box<String>),
+    Throw(Box<Expr>),
+    Try(Box<Expr>, Vec<Expr>, Option<Box<Expr>>),
+    Catch(Box<String>, Box<String>, Box<Expr>),
+    SetBang(Box<Expr>, Box<Expr>),
+    New(Box<String>, Vec<Expr>),
+    HostInterop(Box<Expr>, Box<String>, Vec<Expr>),
+    Invoke(Box<Expr>, Vec<Expr>)
+}
+
+/* Where the value of an expression ends up, recur is only allowed in return position */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Context {
+    Statement,
+    Expression,
+    Return
+}
+
+pub trait SemanticAnalyzerMethods {
+    fn new() -> Self;
+    fn analyze_unit(&mut self, unit: &Node) -> Result<Vec<Expr>, AnalyzerError>;
+    fn analyze(&mut self, node: &Node, context: Context) -> Result<Expr, AnalyzerError>;
+    fn analyze_body(&mut self, nodes: &[Node], context: Context) -> Result<Expr, AnalyzerError>;
+    fn analyze_symbol(&mut self, namespace: &Option<Box<String>>, name: &str) -> Expr;
+    fn analyze_seq(&mut self, span: Span, nodes: &[Node], context: Context) -> Result<Expr, AnalyzerError>;
+    fn analyze_def(&mut self, span: Span, args: &[Node]) -> Result<Expr, AnalyzerError>;
+    fn analyze_if(&mut self, span: Span, args: &[Node], context: Context) -> Result<Expr, AnalyzerError>;
+    fn analyze_let(&mut self, span: Span, args: &[Node], context: Context, is_loop: bool) -> Result<Expr, AnalyzerError>;
+    fn analyze_recur(&mut self, span: Span, args: &[Node], context: Context) -> Result<Expr, AnalyzerError>;
+    fn analyze_fn(&mut self, span: Span, args: &[Node]) -> Result<Expr, AnalyzerError>;
+    fn analyze_fn_method(&mut self, params: &Node, body: &[Node]) -> Result<Expr, AnalyzerError>;
+    fn analyze_try(&mut self, args: &[Node], context: Context) -> Result<Expr, AnalyzerError>;
+    fn analyze_host_interop(&mut self, span: Span, args: &[Node]) -> Result<Expr, AnalyzerError>;
+    fn is_local(&self, name: &str) -> bool;
+}
+
+#[allow(clippy::vec_box)]
+pub struct SemanticAnalyzer {
+    locals: Vec<Box<String>>,
+    recur_targets: Vec<Option<usize>>
+}
+
+impl SemanticAnalyzerMethods for SemanticAnalyzer {
+
+    fn new() -> Self {
+        SemanticAnalyzer {
+            locals: Vec::new(),
+            recur_targets: Vec::new()
+        }
+    }
+
+    fn analyze_unit(&mut self, unit: &Node) -> Result<Vec<Expr>, AnalyzerError> {
+        match unit {
+            Node::Unit(_, nodes) => nodes.iter().map(|x| self.analyze(x, Context::Statement)).collect(),
+            _ => Err(AnalyzerError::ExpectingUnit(unit.span()))
+        }
+    }
+
+    /* Metadata is for the compiler and tools, it does not change what the form evaluates to, so the metadata slots are skipped */
+    fn analyze(&mut self, node: &Node, context: Context) -> Result<Expr, AnalyzerError> {
+        match node {
+            Node::Symbol(_, namespace, name, _) => Ok(self.analyze_symbol(namespace, name)),
+            Node::Nil(_) | Node::Bool(_, _) | Node::Long(_, _) | Node::Double(_, _) | Node::BigInt(_, _) | Node::Ratio(_, _, _) |
+            Node::BigRatio(_, _, _) | Node::BigDecimal(_, _) | Node::String(_, _) | Node::Char(_, _) | Node::Keyword(_, _, _) |
+            Node::AutoResolvedKeyword(_, _, _) | Node::Regex(_, _) | Node::Tagged(_, _) => Ok(Expr::Constant(node.clone())),
+            Node::List(_, nodes, _, _) if nodes.is_empty() => Ok(Expr::Constant(node.clone())),
+            Node::List(_, nodes, _, _) => self.analyze_seq(node.span(), nodes, context),
+            Node::Vec(_, nodes, _, _) => {
+                let items = nodes.iter().map(|x| self.analyze(x, Context::Expression)).collect::<Result<Vec<Expr>, AnalyzerError>>()?;
+                Ok(Expr::Vector(items))
+            },
+            Node::Set(_, nodes, _, _) => {
+                let items = nodes.iter().map(|x| self.analyze(x, Context::Expression)).collect::<Result<Vec<Expr>, AnalyzerError>>()?;
+                Ok(Expr::Set(items))
+            },
+            Node::Map(_, entries, _, _) => {
+                let mut items : Vec<(Expr, Expr)> = Vec::new();
+                for entry in entries {
+                    match entry {
+                        Node::KeyValue(key, value) => {
+                            items.push((self.analyze(key, Context::Expression)?, self.analyze(value, Context::Expression)?))
+                        },
+                        x => return Err(AnalyzerError::ExpectingKeyValue(x.span()))
+                    }
+                }
+                Ok(Expr::Map(items))
+            },
+            Node::Error(span) => Err(AnalyzerError::InvalidSyntax(*span)),
+            Node::Conditional(_, _) => Err(AnalyzerError::UnexpandedConditional(node.span())),
+            Node::KeyValue(_, _) | Node::Unit(_, _) => Err(AnalyzerError::UnexpectedNode(node.span()))
+        }
+    }
+
+    fn analyze_body(&mut self, nodes: &[Node], context: Context) -> Result<Expr, AnalyzerError> {
+        let mut body : Vec<Expr> = Vec::new();
+        for (index, node) in nodes.iter().enumerate() {
+            let position = if index + 1 == nodes.len() { context } else { Context::Statement };
+            body.push(self.analyze(node, position)?)
+        }
+        Ok(Expr::Do(body))
+    }
+
+    fn analyze_symbol(&mut self, namespace: &Option<Box<String>>, name: &str) -> Expr {
+        match namespace {
+            None if self.is_local(name) => Expr::Local(Box::new(name.to_string())),
+            _ => Expr::Var(namespace.clone(), Box::new(name.to_string()))
+        }
+    }
+
+    fn analyze_seq(&mut self, span: Span, nodes: &[Node], context: Context) -> Result<Expr, AnalyzerError> {
+        let args = &nodes[1..];
+
+        /* Special forms can not be shadowed by locals, just like in Clojure */
+        let special = match &nodes[0] {
+            Node::Symbol(_, None, name, _) if SPECIAL_FORMS.contains(&name.as_str()) => Some(name.as_str()),
+            _ => None
+        };
+
+        match special {
+            Some("def") => self.analyze_def(span, args),
+            Some("if") => self.analyze_if(span, args, context),
+            Some("do") => self.analyze_body(args, context),
+            Some("let*") => self.analyze_let(span, args, context, false),
+            Some("loop*") => self.analyze_let(span, args, context, true),
+            Some("recur") => self.analyze_recur(span, args, context),
+            Some("fn*") => self.analyze_fn(span, args),
+            Some("quote") => {
+                match args {
+                    [form] => Ok(Expr::Quote(form.clone())),
+                    _ => Err(AnalyzerError::WrongNumberOfArgs(span, Box::new("quote".to_string())))
+                }
+            },
+            Some("var") => {
+                match args {
+                    [Node::Symbol(_, namespace, name, _)] => Ok(Expr::TheVar(namespace.clone(), name.clone())),
+                    _ => Err(AnalyzerError::VarNotSymbol(span))
+                }
+            },
+            Some("throw") => {
+                match args {
+                    [form] => Ok(Expr::Throw(Box::new(self.analyze(form, Context::Expression)?))),
+                    _ => Err(AnalyzerError::WrongNumberOfArgs(span, Box::new("throw".to_string())))
+                }
+            },
+            Some("try") => self.analyze_try(args, context),
+            Some("set!") => {
+                match args {
+                    [target, value] => {
+                        let analyzed = self.analyze(target, Context::Expression)?;
+                        if let Expr::Local(name) = &analyzed {
+                            return Err(AnalyzerError::AssignToLocal(target.span(), name.clone()))
+                        }
+                        Ok(Expr::SetBang(Box::new(analyzed), Box::new(self.analyze(value, Context::Expression)?)))
+                    },
+                    _ => Err(AnalyzerError::WrongNumberOfArgs(span, Box::new("set!".to_string())))
+                }
+            },
+            Some("new") => {
+                match args.split_first() {
+                    Some((symbol @ Node::Symbol(_, _, _, _), rest)) => {
+                        let rest = rest.iter().map(|x| self.analyze(x, Context::Expression)).collect::<Result<Vec<Expr>, AnalyzerError>>()?;
+                        Ok(Expr::New(Box::new(symbol.to_string()), rest))
+                    },
+                    _ => Err(AnalyzerError::MissingClassName(span))
+                }
+            },
+            Some(_) => self.analyze_host_interop(span, args),
+            None => {
+                let function = self.analyze(&nodes[0], Context::Expression)?;
+                let args = args.iter().map(|x| self.analyze(x, Context::Expression)).collect::<Result<Vec<Expr>, AnalyzerError>>()?;
+                Ok(Expr::Invoke(Box::new(function), args))
+            }
+        }
+    }
+
+    fn analyze_def(&mut self, span: Span, args: &[Node]) -> Result<Expr, AnalyzerError> {
+        let (name, init) = match args {
+            [Node::Symbol(_, None, name, _)] => (name, None),
+            [Node::Symbol(_, None, name, _), init] => (name, Some(init)),
+            [Node::Symbol(_, None, name, _), Node::String(_, _), init] => (name, Some(init)),
+            [Node::Symbol(name_span, Some(_), _, _), ..] => return Err(AnalyzerError::DefOutsideNamespace(*name_span)),
+            [_, _, _, _, ..] => return Err(AnalyzerError::TooManyArguments(span, Box::new("def".to_string()))),
+            [] => return Err(AnalyzerError::TooFewArguments(span, Box::new("def".to_string()))),
+            [x, ..] => return Err(AnalyzerError::DefNameNotSymbol(x.span()))
+        };
+
+        let init = match init {
+            Some(x) => Some(Box::new(self.analyze(x, Context::Expression)?)),
+            None => None
+        };
+        Ok(Expr::Def(name.clone(), init))
+    }
+
+    fn analyze_if(&mut self, span: Span, args: &[Node], context: Context) -> Result<Expr, AnalyzerError> {
+        match args {
+            [test, then] | [test, then, _] => {
+                let test = self.analyze(test, Context::Expression)?;
+                let then = self.analyze(then, context)?;
+                let otherwise = match args.get(2) {
+                    Some(x) => Some(Box::new(self.analyze(x, context)?)),
+                    None => None
+                };
+                Ok(Expr::If(Box::new(test), Box::new(then), otherwise))
+            },
+            [_, _, _, _, ..] => Err(AnalyzerError::TooManyArguments(span, Box::new("if".to_string()))),
+            _ => Err(AnalyzerError::TooFewArguments(span, Box::new("if".to_string())))
+        }
+    }
+
+    fn analyze_let(&mut self, span: Span, args: &[Node], context: Context, is_loop: bool) -> Result<Expr, AnalyzerError> {
+        let form = if is_loop { "loop*" } else { "let*" };
+        let (bindings, body) = match args.split_first() {
+            Some((Node::Vec(_, bindings, _, _), body)) => (bindings, body),
+            Some((x, _)) => return Err(AnalyzerError::BindingsNotVector(x.span(), Box::new(form.to_string()))),
+            None => return Err(AnalyzerError::BindingsNotVector(span, Box::new(form.to_string())))
+        };
+        if bindings.len() % 2 != 0 {
+            return Err(AnalyzerError::OddBindingForms(args[0].span(), Box::new(form.to_string())))
+        }
+
+        let scope = self.locals.len();
+        let mut analyzed : Vec<(Box<String>, Expr)> = Vec::new();
+        for pair in bindings.chunks(2) {
+            let name = match &pair[0] {
+                Node::Symbol(_, None, name, _) => name.clone(),
+                x => {
+                    self.locals.truncate(scope);
+                    return Err(AnalyzerError::BindingNotSymbol(x.span(), Box::new(x.to_string())))
+                }
+            };
+            let init = self.analyze(&pair[1], Context::Expression);
+            match init {
+                Ok(x) => analyzed.push((name.clone(), x)),
+                Err(x) => {
+                    self.locals.truncate(scope);
+                    return Err(x)
+                }
+            }
+            self.locals.push(name)
+        }
+
+        let body = match is_loop {
+            true => {
+                self.recur_targets.push(Some(analyzed.len()));
+                let body = self.analyze_body(body, Context::Return);
+                self.recur_targets.pop();
+                body
+            },
+            false => self.analyze_body(body, context)
+        };
+        self.locals.truncate(scope);
+
+        match is_loop {
+            true => Ok(Expr::Loop(analyzed, Box::new(body?))),
+            false => Ok(Expr::Let(analyzed, Box::new(body?)))
+        }
+    }
+
+    fn analyze_recur(&mut self, span: Span, args: &[Node], context: Context) -> Result<Expr, AnalyzerError> {
+        let arity = match (context, self.recur_targets.last()) {
+            (Context::Return, Some(Some(x))) => *x,
+            (Context::Return, Some(None)) => return Err(AnalyzerError::RecurAcrossTry(span)),
+            (Context::Return, None) => return Err(AnalyzerError::RecurOutsideLoop(span)),
+            _ => return Err(AnalyzerError::RecurNotInTail(span))
+        };
+        if args.len() != arity {
+            return Err(AnalyzerError::RecurArityMismatch(span, arity, args.len()))
+        }
+
+        let args = args.iter().map(|x| self.analyze(x, Context::Expression)).collect::<Result<Vec<Expr>, AnalyzerError>>()?;
+        Ok(Expr::Recur(args))
+    }
+
+    fn analyze_fn(&mut self, span: Span, args: &[Node]) -> Result<Expr, AnalyzerError> {
+        let (name, methods) = match args.split_first() {
+            Some((Node::Symbol(_, None, name, _), rest)) => (Some(name.clone()), rest),
+            _ => (None, args)
+        };
+
+        /* The name of the function is visible inside its body for self recursion */
+        let scope = self.locals.len();
+        if let Some(x) = &name {
+            self.locals.push(x.clone())
+        }
+
+        let result = match methods {
+            [params @ Node::Vec(_, _, _, _), body @ ..] => self.analyze_fn_method(params, body).map(|x| vec![x]),
+            [] => Err(AnalyzerError::MissingParameterVector(span)),
+            _ => {
+                let mut analyzed : Vec<Expr> = Vec::new();
+                for method in methods {
+                    match method {
+                        Node::List(_, nodes, _, _) if !nodes.is_empty() => {
+                            match self.analyze_fn_method(&nodes[0], &nodes[1..]) {
+                                Ok(x) => analyzed.push(x),
+                                Err(x) => {
+                                    self.locals.truncate(scope);
+                                    return Err(x)
+                                }
+                            }
+                        },
+                        x => {
+                            self.locals.truncate(scope);
+                            return Err(AnalyzerError::InvalidFnMethod(x.span()))
+                        }
+                    }
+                }
+                Ok(analyzed)
+            }
+        };
+        self.locals.truncate(scope);
+
+        Ok(Expr::Fn(name, result?))
+    }
+
+    fn analyze_fn_method(&mut self, params: &Node, body: &[Node]) -> Result<Expr, AnalyzerError> {
+        let params = match params {
+            Node::Vec(_, params, _, _) => params,
+            x => return Err(AnalyzerError::ParametersNotVector(x.span()))
+        };
+
+        let mut required : Vec<Box<String>> = Vec::new();
+        let mut rest : Option<Box<String>> = None;
+        let mut iter = params.iter();
+        while let Some(param) = iter.next() {
+            match param {
+                Node::Symbol(_, None, name, _) if name.as_str() == "&" => {
+                    match (iter.next(), iter.next()) {
+                        (Some(Node::Symbol(_, None, name, _)), None) => rest = Some(name.clone()),
+                        _ => return Err(AnalyzerError::InvalidRestParameter(param.span()))
+                    }
+                },
+                Node::Symbol(_, None, name, _) => required.push(name.clone()),
+                x => return Err(AnalyzerError::UnsupportedBindingForm(x.span(), Box::new(x.to_string())))
+            }
+        }
+
+        let scope = self.locals.len();
+        self.locals.extend(required.iter().cloned());
+        self.locals.extend(rest.iter().cloned());
+        self.recur_targets.push(Some(required.len() + rest.iter().count()));
+        let body = self.analyze_body(body, Context::Return);
+        self.recur_targets.pop();
+        self.locals.truncate(scope);
+
+        Ok(Expr::FnMethod(required, rest, Box::new(body?)))
+    }
+
+    fn analyze_try(&mut self, args: &[Node], context: Context) -> Result<Expr, AnalyzerError> {
+        let clause = |node: &Node| -> Option<String> {
+            match node {
+                Node::List(_, nodes, _, _) => match nodes.first() {
+                    Some(Node::Symbol(_, None, name, _)) if name.as_str() == "catch" || name.as_str() == "finally" => Some(name.to_string()),
+                    _ => None
+                },
+                _ => None
+            }
+        };
+
+        let split = args.iter().position(|x| clause(x).is_some()).unwrap_or(args.len());
+        let (body, clauses) = args.split_at(split);
+
+        self.recur_targets.push(None);
+        let result = (|| {
+            let body = self.analyze_body(body, context)?;
+            let mut catches : Vec<Expr> = Vec::new();
+            let mut finally : Option<Box<Expr>> = None;
+
+            for (index, node) in clauses.iter().enumerate() {
+                let nodes = match node {
+                    Node::List(_, nodes, _, _) => nodes,
+                    x => return Err(AnalyzerError::InvalidTryClause(x.span()))
+                };
+                match (clause(node).as_deref(), &nodes[1..]) {
+                    (Some("catch"), [class @ Node::Symbol(_, _, _, _), Node::Symbol(_, None, local, _), handler @ ..]) if finally.is_none() => {
+                        self.locals.push(local.clone());
+                        let handler = self.analyze_body(handler, context);
+                        self.locals.pop();
+                        catches.push(Expr::Catch(Box::new(class.without_meta().to_string()), local.clone(), Box::new(handler?)))
+                    },
+                    (Some("catch"), _) if finally.is_none() => return Err(AnalyzerError::InvalidCatch(node.span())),
+                    (Some("finally"), rest) if index + 1 == clauses.len() => {
+                        finally = Some(Box::new(self.analyze_body(rest, Context::Statement)?))
+                    },
+                    (Some("finally"), _) => return Err(AnalyzerError::FinallyNotLast(node.span())),
+                    _ => return Err(AnalyzerError::InvalidTryClause(node.span()))
+                }
+            }
+            Ok(Expr::Try(Box::new(body), catches, finally))
+        })();
+        self.recur_targets.pop();
+
+        result
+    }
+
+    fn analyze_host_interop(&mut self, span: Span, args: &[Node]) -> Result<Expr, AnalyzerError> {
+        let (target, member) = match args {
+            [target, member, ..] => (target, member),
+            _ => return Err(AnalyzerError::MalformedMemberExpression(span))
+        };
+        let target = self.analyze(target, Context::Expression)?;
+
+        /* Both (. target member args...) and (. target (member args...)) are allowed */
+        let (member, rest) = match (member, &args[2..]) {
+            (Node::Symbol(_, None, name, _), rest) => (name.clone(), rest),
+            (Node::List(_, nodes, _, _), []) => match nodes.split_first() {
+                Some((Node::Symbol(_, None, name, _), rest)) => (name.clone(), rest),
+                _ => return Err(AnalyzerError::MemberNotSymbol(member.span()))
+            },
+            _ => return Err(AnalyzerError::MemberNotSymbol(member.span()))
+        };
+
+        let rest = rest.iter().map(|x| self.analyze(x, Context::Expression)).collect::<Result<Vec<Expr>, AnalyzerError>>()?;
+        Ok(Expr::HostInterop(Box::new(target), member, rest))
+    }
+
+    fn is_local(&self, name: &str) -> bool {
+        self.locals.iter().any(|x| x.as_str() == name)
+    }
+}
+
+impl Default for SemanticAnalyzer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::analyzer_error::AnalyzerError;
+    use crate::parser::{expression_parser::{ExpressionParser, ExpressionParserMethods}, lexical_analyzer::{LexicalAnalyzer, LexicalAnalyzerMethods}};
+    use crate::parser::source_position::{ Position, Span };
+
+    use super::{Expr, SemanticAnalyzer, SemanticAnalyzerMethods};
+
+    fn analyze(text: &str) -> Result<Vec<Expr>, AnalyzerError> {
+        let mut parser = ExpressionParser::new(LexicalAnalyzer::new(text));
+        SemanticAnalyzer::new().analyze_unit(&parser.parse_start_unit().unwrap())
+    }
+
+    fn p(offset: u32) -> Position {
+        Position::new(0, 1, offset + 1, offset)
+    }
+
+    #[allow(clippy::box_collection)]
+    fn name(text: &str) -> Box<String> {
+        Box::new(text.to_string())
+    }
+
+    #[test]
+    fn def_and_invoke() {
+        let res = analyze("(def x (inc 1))");
+
+        match res {
+            Ok(x) => match &x[..] {
+                [Expr::Def(n, Some(init))] => {
+                    assert_eq!(n, &name("x"));
+                    assert!(matches!(init.as_ref(), Expr::Invoke(f, args) if **f == Expr::Var(None, name("inc")) && args.len() == 1))
+                },
+                x => panic!("Unexpected result {:?}", x)
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn def_with_doc_string() {
+        assert!(matches!(&analyze("(def x \"doc\" 1)").unwrap()[..], [Expr::Def(_, Some(_))]));
+        assert!(matches!(analyze("(def a/x 1)"), Err(AnalyzerError::DefOutsideNamespace(_))));
+        assert!(matches!(analyze("(def)"), Err(AnalyzerError::TooFewArguments(_, form)) if form.as_str() == "def"));
+        assert!(matches!(analyze("(def 1 2)"), Err(AnalyzerError::DefNameNotSymbol(_))))
+    }
+
+    #[test]
+    fn locals_shadow_core_names() {
+        let res = analyze("(let* [map inc name 1] (map name))").unwrap();
+
+        match &res[..] {
+            [Expr::Let(bindings, body)] => {
+                assert_eq!(bindings.len(), 2);
+                assert_eq!(**body, Expr::Do(vec![Expr::Invoke(Box::new(Expr::Local(name("map"))), vec![Expr::Local(name("name"))])]))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn locals_go_out_of_scope() {
+        let res = analyze("[(let* [x 1] x) x]").unwrap();
+
+        match &res[..] {
+            [Expr::Vector(items)] => {
+                assert!(matches!(&items[0], Expr::Let(_, body) if **body == Expr::Do(vec![Expr::Local(name("x"))])));
+                assert_eq!(items[1], Expr::Var(None, name("x")))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn qualified_symbol_is_never_local() {
+        let res = analyze("(fn* [x] user/x)").unwrap();
+
+        match &res[..] {
+            [Expr::Fn(None, methods)] => {
+                assert_eq!(methods[0], Expr::FnMethod(vec![name("x")], None, Box::new(Expr::Do(vec![Expr::Var(Some(name("user")), name("x"))]))))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn special_form_not_recognized_when_qualified() {
+        let res = analyze("(my/if 1 2)").unwrap();
+
+        assert!(matches!(&res[..], [Expr::Invoke(_, args)] if args.len() == 2))
+    }
+
+    #[test]
+    fn if_arity() {
+        assert!(matches!(&analyze("(if a b)").unwrap()[..], [Expr::If(_, _, None)]));
+        assert!(matches!(&analyze("(if a b c)").unwrap()[..], [Expr::If(_, _, Some(_))]));
+        assert!(matches!(analyze("(if a)"), Err(AnalyzerError::TooFewArguments(_, form)) if form.as_str() == "if"));
+        assert!(matches!(analyze("(if a b c d)"), Err(AnalyzerError::TooManyArguments(_, form)) if form.as_str() == "if"))
+    }
+
+    #[test]
+    fn let_bad_bindings() {
+        assert!(matches!(analyze("(let* [a] a)"), Err(AnalyzerError::OddBindingForms(_, _))));
+        assert!(matches!(analyze("(let* (a 1) a)"), Err(AnalyzerError::BindingsNotVector(_, _))));
+        assert!(matches!(analyze("(let* [:a 1] a)"), Err(AnalyzerError::BindingNotSymbol(_, _))))
+    }
+
+    #[test]
+    fn fn_with_name_and_variadic_methods() {
+        let res = analyze("(fn* self ([] (self 1)) ([a & more] (recur a more)))").unwrap();
+
+        match &res[..] {
+            [Expr::Fn(Some(n), methods)] => {
+                assert_eq!(n, &name("self"));
+                assert_eq!(methods.len(), 2);
+                match &methods[0] {
+                    Expr::FnMethod(required, None, body) if required.is_empty() => {
+                        assert!(matches!(body.as_ref(), Expr::Do(x) if matches!(&x[..], [Expr::Invoke(f, _)] if **f == Expr::Local(name("self")))))
+                    },
+                    x => panic!("Unexpected method {:?}", x)
+                }
+                assert!(matches!(&methods[1], Expr::FnMethod(required, Some(rest), _) if required.len() == 1 && rest == &name("more")))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn loop_and_recur() {
+        assert!(matches!(&analyze("(loop* [i 0] (if i (recur (inc i)) i))").unwrap()[..], [Expr::Loop(_, _)]));
+        assert!(matches!(analyze("(loop* [i 0 j 1] (recur i))"), Err(AnalyzerError::RecurArityMismatch(_, 2, 1))));
+        assert!(matches!(analyze("(loop* [i 0] (do (recur i) i))"), Err(AnalyzerError::RecurNotInTail(_))));
+        assert!(matches!(analyze("(loop* [i 0] (inc (recur i)))"), Err(AnalyzerError::RecurNotInTail(_))));
+        assert!(matches!(analyze("(recur 1)"), Err(AnalyzerError::RecurNotInTail(_))));
+        assert!(matches!(analyze("(fn* [] (try (recur) (finally 1)))"), Err(AnalyzerError::RecurAcrossTry(_))))
+    }
+
+    #[test]
+    fn metadata_is_ignored() {
+        assert!(matches!(&analyze("(def ^:private x ^{:doc \"d\"} [1])").unwrap()[..], [Expr::Def(n, Some(init))] if n.as_str() == "x" && matches!(init.as_ref(), Expr::Vector(_))));
+        assert!(matches!(&analyze("(fn* f ^[long] [^long a] a)").unwrap()[..], [Expr::Fn(Some(_), methods)] if matches!(&methods[..], [Expr::FnMethod(_, None, _)])));
+        assert!(matches!(&analyze("(let* [^String s 1] s)").unwrap()[..], [Expr::Let(_, body)] if **body == Expr::Do(vec![Expr::Local(name("s"))])))
+    }
+
+    #[test]
+    fn quote_var_and_throw() {
+        assert!(matches!(&analyze("(quote (a b))").unwrap()[..], [Expr::Quote(_)]));
+        assert!(matches!(&analyze("'(a b)").unwrap()[..], [Expr::Quote(_)]));
+        assert_eq!(analyze("(var clojure.core/map)").unwrap(), vec![Expr::TheVar(Some(name("clojure.core")), name("map"))]);
+        assert_eq!(analyze("#'clojure.core/map").unwrap(), vec![Expr::TheVar(Some(name("clojure.core")), name("map"))]);
+        assert!(matches!(&analyze("#(inc %)").unwrap()[..], [Expr::Fn(None, methods)] if methods.len() == 1));
+        assert!(matches!(&analyze("(throw ex)").unwrap()[..], [Expr::Throw(_)]));
+        assert!(matches!(analyze("(quote a b)"), Err(AnalyzerError::WrongNumberOfArgs(_, form)) if form.as_str() == "quote"))
+    }
+
+    #[test]
+    fn try_catch_finally() {
+        let res = analyze("(try (risky) (catch Exception e (log e)) (finally (cleanup)))").unwrap();
+
+        match &res[..] {
+            [Expr::Try(_, catches, Some(_))] => {
+                assert_eq!(catches[0], Expr::Catch(name("Exception"), name("e"), Box::new(Expr::Do(vec![
+                    Expr::Invoke(Box::new(Expr::Var(None, name("log"))), vec![Expr::Local(name("e"))])
+                ]))))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+
+        assert!(matches!(analyze("(try 1 (finally 2) (catch E e 3))"), Err(AnalyzerError::FinallyNotLast(_))));
+        assert!(matches!(analyze("(try 1 (catch E e 3) 4)"), Err(AnalyzerError::InvalidTryClause(_))))
+    }
+
+    #[test]
+    fn set_new_and_host_interop() {
+        assert!(matches!(&analyze("(set! *warn* 1)").unwrap()[..], [Expr::SetBang(_, _)]));
+        assert!(matches!(analyze("(let* [a 1] (set! a 2))"), Err(AnalyzerError::AssignToLocal(_, n)) if n == name("a")));
+        assert!(matches!(&analyze("(new java.util.Date 1)").unwrap()[..], [Expr::New(class, args)] if class.as_str() == "java.util.Date" && args.len() == 1));
+        assert!(matches!(&analyze("(. s (substring 1 2))").unwrap()[..], [Expr::HostInterop(_, member, args)] if member.as_str() == "substring" && args.len() == 2));
+        assert!(matches!(&analyze("(. Math PI)").unwrap()[..], [Expr::HostInterop(_, member, args)] if member.as_str() == "PI" && args.is_empty()))
+    }
+
+    #[test]
+    fn errors_point_at_offending_form() {
+        assert_eq!(analyze("(let* [:a 1] a)").unwrap_err().span(), Span::new(p(7), p(9)));
+        assert_eq!(analyze("(do (if a))").unwrap_err().span(), Span::new(p(4), p(10)));
+        assert_eq!(analyze("(fn* [a & 1] a)").unwrap_err().span(), Span::new(p(8), p(9)));
+        assert_eq!(analyze("(. s :k)").unwrap_err().span(), Span::new(p(5), p(7)))
+    }
+}
\ No newline at end of file
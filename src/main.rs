@@ -1,5 +1,5 @@
-
-mod parser;
+pub mod parser;
+pub mod analyzer;
 
 fn main() {
     println!("Hello, world!");
@@ -1,84 +1,1168 @@
 use super::lexical_analyzer::{ LexicalAnalyzer, LexicalAnalyzerMethods, Symbols };
+use super::source_position::{ Position, Span };
+use super::syntax_error::SyntaxError;
+use std::collections::{ HashMap, HashSet };
+use std::rc::Rc;
 
+/* Symbols the compiler handles itself, syntax quote leaves them unqualified */
+const SYNTAX_QUOTE_SPECIALS: [&str; 24] = [
+    "def", "loop*", "recur", "if", "case*", "let*", "letfn*", "do", "fn*", "quote", "var", "import*", ".",
+    "set!", "deftype*", "reify*", "try", "throw", "monitor-enter", "monitor-exit", "catch", "finally", "new", "&"
+];
+
+/* Called with the Node::Tagged form, returns the node to read in its place or a message explaining why the form is invalid */
+pub type TagReader = Rc<dyn Fn(Node) -> Result<Node, String>>;
+
+/* Collections and symbols end with their metadata map, the only forms that can carry metadata */
 #[derive(Clone, PartialEq, Debug)]
 pub enum Node {
-    Unit(Vec<Node>),
-    List(Symbols, Vec<Node>, Symbols),
-    Vec(Symbols, Vec<Node>, Symbols),
-    Map(Symbols, Vec<Node>, Vec::<Node>, Symbols),
-    Set(Symbols, Vec<Node>, Symbols),
-    KeyValue(Symbols, Symbols),
-    Element(Symbols)
+    Unit(Span, Vec<Node>),
+    List(Symbols, Vec<Node>, Symbols, Option<Box<Node>>),
+    Vec(Symbols, Vec<Node>, Symbols, Option<Box<Node>>),
+    Map(Symbols, Vec<Node>, Symbols, Option<Box<Node>>),
+    Set(Symbols, Vec<Node>, Symbols, Option<Box<Node>>),
+    KeyValue(Box<Node>, Box<Node>),
+    Conditional(Symbols, Box<Node>),
+    Tagged(Symbols, Box<Node>),
+    Nil(Span),
+    Bool(Span, bool),
+    Long(Span, i64),
+    Double(Span, f64),
+    BigInt(Span, Box<String>),
+    Ratio(Span, i64, i64),
+    BigRatio(Span, Box<String>, Box<String>),
+    BigDecimal(Span, Box<String>),
+    String(Span, Box<String>),
+    Char(Span, char),
+    Keyword(Span, Option<Box<String>>, Box<String>),
+    AutoResolvedKeyword(Span, Option<Box<String>>, Box<String>),
+    Symbol(Span, Option<Box<String>>, Box<String>, Option<Box<Node>>),
+    Regex(Span, Box<String>),
+    Error(Span)
 }
 
-pub trait ExpressionParserMethods {
-    fn new(lexer: LexicalAnalyzer) -> Self;
+impl Node {
+    pub fn span(&self) -> Span {
+        match self {
+            Node::Unit(span, _) |
+            Node::Nil(span) |
+            Node::Bool(span, _) |
+            Node::Long(span, _) |
+            Node::Double(span, _) |
+            Node::BigInt(span, _) |
+            Node::Ratio(span, _, _) |
+            Node::BigRatio(span, _, _) |
+            Node::BigDecimal(span, _) |
+            Node::String(span, _) |
+            Node::Char(span, _) |
+            Node::Keyword(span, _, _) |
+            Node::AutoResolvedKeyword(span, _, _) |
+            Node::Symbol(span, _, _, _) |
+            Node::Regex(span, _) |
+            Node::Error(span) => *span,
+            Node::List(start, _, end, _) |
+            Node::Vec(start, _, end, _) |
+            Node::Map(start, _, end, _) |
+            Node::Set(start, _, end, _) => start.span().merge(&end.span()),
+            Node::KeyValue(key, value) => key.span().merge(&value.span()),
+            Node::Conditional(start, form) |
+            Node::Tagged(start, form) => start.span().merge(&form.span())
+        }
+    }
+
+    /* Typed node for a literal token, None for tokens that are not literals */
+    pub fn literal(symbol: Symbols) -> Option<Node> {
+        let span = symbol.span();
+        match symbol {
+            Symbols::LiteralNil(_, _) => Some(Node::Nil(span)),
+            Symbols::LiteralBoolean(_, _, value) => Some(Node::Bool(span, value)),
+            Symbols::LiteralInteger(_, _, value) => Some(Node::Long(span, value)),
+            Symbols::LiteralFloat(_, _, value) => Some(Node::Double(span, value)),
+            Symbols::LiteralBigInteger(_, _, value) => Some(Node::BigInt(span, value)),
+            Symbols::LiteralRatio(_, _, numerator, denominator) => Some(Node::Ratio(span, numerator, denominator)),
+            Symbols::LiteralBigRatio(_, _, numerator, denominator) => Some(Node::BigRatio(span, numerator, denominator)),
+            Symbols::LiteralBigDecimal(_, _, value) => Some(Node::BigDecimal(span, value)),
+            Symbols::LiteralString(_, _, text) => Some(Node::String(span, text)),
+            Symbols::LiteralChar(_, _, ch) => Some(Node::Char(span, ch)),
+            Symbols::LiteralKeyword(_, _, namespace, name) => Some(Node::Keyword(span, namespace, name)),
+            Symbols::LiteralAutoResolvedKeyword(_, _, namespace, name) => Some(Node::AutoResolvedKeyword(span, namespace, name)),
+            Symbols::LiteralName(_, _, namespace, name) => Some(Node::Symbol(span, namespace, name, None)),
+            Symbols::LiteralRegex(_, _, text) => Some(Node::Regex(span, text)),
+            _ => None
+        }
+    }
+
+    /* The literal token a typed node was read from, None for collections and other compound forms */
+    pub fn token(&self) -> Option<Symbols> {
+        match self.clone() {
+            Node::Nil(span) => Some(Symbols::LiteralNil(span.start, span.end)),
+            Node::Bool(span, value) => Some(Symbols::LiteralBoolean(span.start, span.end, value)),
+            Node::Long(span, value) => Some(Symbols::LiteralInteger(span.start, span.end, value)),
+            Node::Double(span, value) => Some(Symbols::LiteralFloat(span.start, span.end, value)),
+            Node::BigInt(span, value) => Some(Symbols::LiteralBigInteger(span.start, span.end, value)),
+            Node::Ratio(span, numerator, denominator) => Some(Symbols::LiteralRatio(span.start, span.end, numerator, denominator)),
+            Node::BigRatio(span, numerator, denominator) => Some(Symbols::LiteralBigRatio(span.start, span.end, numerator, denominator)),
+            Node::BigDecimal(span, value) => Some(Symbols::LiteralBigDecimal(span.start, span.end, value)),
+            Node::String(span, text) => Some(Symbols::LiteralString(span.start, span.end, text)),
+            Node::Char(span, ch) => Some(Symbols::LiteralChar(span.start, span.end, ch)),
+            Node::Keyword(span, namespace, name) => Some(Symbols::LiteralKeyword(span.start, span.end, namespace, name)),
+            Node::AutoResolvedKeyword(span, namespace, name) => Some(Symbols::LiteralAutoResolvedKeyword(span.start, span.end, namespace, name)),
+            Node::Symbol(span, namespace, name, _) => Some(Symbols::LiteralName(span.start, span.end, namespace, name)),
+            Node::Regex(span, text) => Some(Symbols::LiteralRegex(span.start, span.end, text)),
+            _ => None
+        }
+    }
+
+    /* The metadata map attached with ^ or added by the reader, None when the form has no metadata */
+    pub fn metadata(&self) -> Option<&Node> {
+        match self {
+            Node::List(_, _, _, metadata) |
+            Node::Vec(_, _, _, metadata) |
+            Node::Map(_, _, _, metadata) |
+            Node::Set(_, _, _, metadata) |
+            Node::Symbol(_, _, _, metadata) => metadata.as_deref(),
+            _ => None
+        }
+    }
+
+    /* The metadata slot of collections and symbols, None for forms that cannot carry metadata */
+    pub fn metadata_mut(&mut self) -> Option<&mut Option<Box<Node>>> {
+        match self {
+            Node::List(_, _, _, metadata) |
+            Node::Vec(_, _, _, metadata) |
+            Node::Map(_, _, _, metadata) |
+            Node::Set(_, _, _, metadata) |
+            Node::Symbol(_, _, _, metadata) => Some(metadata),
+            _ => None
+        }
+    }
+
+    /* Looks up a keyword like "doc" or "private" in the metadata */
+    pub fn meta_value(&self, key: &str) -> Option<&Node> {
+        match self.metadata() {
+            Some(Node::Map(_, entries, _, _)) => entries.iter().find_map(|x| match x {
+                Node::KeyValue(k, v) => match k.as_ref() {
+                    Node::Keyword(_, None, name) if name.as_str() == key => Some(v.as_ref()),
+                    _ => None
+                },
+                _ => None
+            }),
+            _ => None
+        }
+    }
+
+    /* The same form with its metadata dropped, subforms keep theirs */
+    pub fn without_meta(&self) -> Node {
+        let mut node = self.clone();
+        if let Some(metadata) = node.metadata_mut() {
+            *metadata = None
+        }
+        node
+    }
+
+    /* Direct subforms in source order, empty for literals and errors, metadata is not a subform */
+    pub fn children(&self) -> Vec<&Node> {
+        match self {
+            Node::Unit(_, nodes) |
+            Node::List(_, nodes, _, _) |
+            Node::Vec(_, nodes, _, _) |
+            Node::Map(_, nodes, _, _) |
+            Node::Set(_, nodes, _, _) => nodes.iter().collect(),
+            Node::KeyValue(key, value) => vec![key, value],
+            Node::Conditional(_, form) |
+            Node::Tagged(_, form) => vec![form],
+            _ => vec![]
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Node> {
+        match self {
+            Node::Unit(_, nodes) |
+            Node::List(_, nodes, _, _) |
+            Node::Vec(_, nodes, _, _) |
+            Node::Map(_, nodes, _, _) |
+            Node::Set(_, nodes, _, _) => nodes.iter_mut().collect(),
+            Node::KeyValue(key, value) => vec![key, value],
+            Node::Conditional(_, form) |
+            Node::Tagged(_, form) => vec![form],
+            _ => vec![]
+        }
+    }
+
+    /* Same node with its subforms replaced in order, key, value and forms not given are kept, metadata is always kept */
+    pub fn with_children(&self, children: Vec<Node>) -> Node {
+        let mut children = children.into_iter();
+        let mut next = |node: &Node| Box::new(children.next().unwrap_or_else(|| node.clone()));
+        match self {
+            Node::Unit(span, _) => Node::Unit(*span, children.collect()),
+            Node::List(start, _, end, metadata) => Node::List(start.clone(), children.collect(), end.clone(), metadata.clone()),
+            Node::Vec(start, _, end, metadata) => Node::Vec(start.clone(), children.collect(), end.clone(), metadata.clone()),
+            Node::Map(start, _, end, metadata) => Node::Map(start.clone(), children.collect(), end.clone(), metadata.clone()),
+            Node::Set(start, _, end, metadata) => Node::Set(start.clone(), children.collect(), end.clone(), metadata.clone()),
+            Node::KeyValue(key, value) => Node::KeyValue(next(key), next(value)),
+            Node::Conditional(start, form) => Node::Conditional(start.clone(), next(form)),
+            Node::Tagged(tag, form) => Node::Tagged(tag.clone(), next(form)),
+            _ => self.clone()
+        }
+    }
+
+    /* Key that is the same for forms read as equal values, used to find duplicate set elements and map keys.
+     * Metadata is ignored, 1 and 1N or (a b) and [a b] give the same key.
+     * None for forms never equal to anything else, like ##NaN and regular expressions.
+     */
+    pub fn value_key(&self) -> Option<String> {
+        let all = |nodes: &[Node]| nodes.iter().map(|x| x.value_key()).collect::<Option<Vec<String>>>();
+        let sorted = |nodes: &[Node]| all(nodes).map(|mut x| {
+            x.sort();
+            x.join(" ")
+        });
+        match self {
+            Node::List(_, nodes, _, _) | Node::Vec(_, nodes, _, _) => all(nodes).map(|x| format!("[{}]", x.join(" "))),
+            Node::Map(_, nodes, _, _) => sorted(nodes).map(|x| format!("{{{}}}", x)),
+            Node::Set(_, nodes, _, _) => sorted(nodes).map(|x| format!("#{{{}}}", x)),
+            Node::KeyValue(key, value) => Some(format!("{} {}", key.value_key()?, value.value_key()?)),
+            Node::Long(_, value) => Some(format!("{}N", value)),
+            Node::BigInt(_, value) => Some(format!("{}N", value)),
+            Node::Double(_, value) if value.is_nan() => None,
+            Node::Double(_, value) if value.is_infinite() => Some(self.to_string()),
+            Node::Double(_, value) => Some(format!("{:?}", value + 0.0)),
+            Node::BigDecimal(_, value) if !value.contains(['e', 'E']) && value.contains('.') => {
+                Some(format!("{}M", value.trim_end_matches('0').trim_end_matches('.')))
+            },
+            Node::Tagged(tag, form) => Some(format!("#{} {}", tag, form.value_key()?)),
+            Node::Nil(_) | Node::Bool(_, _) | Node::Ratio(_, _, _) | Node::BigRatio(_, _, _) | Node::BigDecimal(_, _) | Node::String(_, _) | Node::Char(_, _) |
+            Node::Keyword(_, _, _) | Node::AutoResolvedKeyword(_, _, _) | Node::Symbol(_, _, _, _) => Some(self.without_meta().to_string()),
+            _ => None
+        }
+    }
+
+    /* Collections whose subforms can be added and removed freely */
+    pub fn is_sequence(&self) -> bool {
+        matches!(self, Node::Unit(_, _) | Node::List(_, _, _, _) | Node::Vec(_, _, _, _) | Node::Map(_, _, _, _) | Node::Set(_, _, _, _))
+    }
+}
+
+pub trait ExpressionParserMethods<'a> {
+    fn new(lexer: LexicalAnalyzer<'a>) -> Self;
+    fn set_namespace(&mut self, namespace: &str);
+    fn set_features(&mut self, features: &[&str]);
+    fn set_preserve_conditionals(&mut self, preserve: bool);
+    fn register_tag_reader(&mut self, tag: &str, reader: impl Fn(Node) -> Result<Node, String> + 'static);
+    fn load_data_readers(&mut self, source: &str) -> Result<(), SyntaxError>;
+    fn set_lexer(&mut self, lexer: LexicalAnalyzer<'a>);
     fn advance(&mut self);
-    fn parse_start_unit(&mut self) -> Result<Node, Box<String>>;
-    fn parse_unit(&mut self) -> Result<Node, Box<String>>;
-    fn parse_list(&mut self) -> Result<Node, Box<String>>;
-    fn parse_vector(&mut self) -> Result<Node, Box<String>>;
-    fn parse_map(&mut self) -> Result<Node, Box<String>>;
+    fn parse_start_unit(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_start_unit_recovering(&mut self) -> (Node, Vec<SyntaxError>);
+    fn recover(&mut self, error: SyntaxError) -> Result<Node, SyntaxError>;
+    fn parse_unit(&mut self, start: Position) -> Result<Node, SyntaxError>;
+    fn parse_start_form(&mut self) -> Result<Option<Node>, SyntaxError>;
+    fn parse_form(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_elements(&mut self) -> Result<(Vec<Node>, Symbols), SyntaxError>;
+    fn parse_list(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_vector(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_map(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_dispatch(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_set(&mut self, start: Position) -> Result<Node, SyntaxError>;
+    fn parse_discard(&mut self) -> Result<(), SyntaxError>;
+    fn parse_anonymous_fn(&mut self, start: Position) -> Result<Node, SyntaxError>;
+    fn parse_var_quote(&mut self, start: Position) -> Result<Node, SyntaxError>;
+    fn parse_tagged(&mut self, start: Position) -> Result<Node, SyntaxError>;
+    fn read_tagged(&self, node: Node) -> Result<Node, SyntaxError>;
+    fn read_tags(&self, node: &Node) -> Result<Node, SyntaxError>;
+    fn parse_quote(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_meta(&mut self) -> Result<Node, SyntaxError>;
+    fn parse_reader_conditional(&mut self, in_collection: bool) -> Result<Vec<Node>, SyntaxError>;
+    fn syntax_quote(&mut self, node: &Node, gensyms: &mut HashMap<String, String>) -> Result<Node, SyntaxError>;
+    fn syntax_quote_elements(&mut self, nodes: &[Node], gensyms: &mut HashMap<String, String>) -> Result<Node, SyntaxError>;
+    fn next_gensym_id(&mut self) -> u32;
 }
 
-pub struct ExpressionParser {
-    lexer: LexicalAnalyzer,
-    symbol: Result<Symbols, Box::<String>>
+#[allow(clippy::box_collection, clippy::vec_box)]
+pub struct ExpressionParser<'a> {
+    lexer: LexicalAnalyzer<'a>,
+    symbol: Result<Symbols, SyntaxError>,
+    namespace: Box<String>,
+    features: Vec<Box<String>>,
+    preserve_conditionals: bool,
+    tag_readers: HashMap<String, TagReader>,
+    suppress_tags: bool,
+    recovering: bool,
+    diagnostics: Vec<SyntaxError>,
+    in_anonymous_fn: bool,
+    gensym_id: u32
 }
 
-impl ExpressionParserMethods for ExpressionParser {
+impl<'a> ExpressionParserMethods<'a> for ExpressionParser<'a> {
 
-    fn new(lexer: LexicalAnalyzer) -> Self {
+    fn new(lexer: LexicalAnalyzer<'a>) -> Self {
         ExpressionParser {
-            lexer: lexer,
-            symbol: Ok(Symbols::EOF)
+            lexer,
+            symbol: Ok(Symbols::EOF(Position::default(), Position::default())),
+            namespace: Box::new("user".to_string()),
+            features: vec![Box::new("native".to_string())],
+            preserve_conditionals: false,
+            tag_readers: default_tag_readers(),
+            suppress_tags: false,
+            recovering: false,
+            diagnostics: Vec::new(),
+            in_anonymous_fn: false,
+            gensym_id: 1
         }
     }
 
+    /* The namespace syntax quote uses for unqualified symbols */
+    fn set_namespace(&mut self, namespace: &str) {
+        *self.namespace = namespace.to_string()
+    }
+
+    /* Platform features selected by reader conditionals, :default always matches */
+    fn set_features(&mut self, features: &[&str]) {
+        self.features = features.iter().map(|x| Box::new(x.to_string())).collect()
+    }
+
+    /* Keep reader conditionals as Node::Conditional instead of selecting a branch, for tools working on .cljc source */
+    fn set_preserve_conditionals(&mut self, preserve: bool) {
+        self.preserve_conditionals = preserve
+    }
+
+    /* Reader used for #tag forms, replaces any earlier reader for the same tag including #inst and #uuid */
+    fn register_tag_reader(&mut self, tag: &str, reader: impl Fn(Node) -> Result<Node, String> + 'static) {
+        self.tag_readers.insert(tag.to_string(), Rc::new(reader));
+    }
+
+    /* Registers the tags of a data_readers.cljc style map like {my/tag my.ns/read-tag}.
+     * The reader function can only run in the compiled program, so #my/tag form reads as (my.ns/read-tag 'form).
+     */
+    fn load_data_readers(&mut self, source: &str) -> Result<(), SyntaxError> {
+        let mut parser = ExpressionParser::new(LexicalAnalyzer::new(source));
+        parser.features = self.features.clone();
+
+        let entries = match parser.parse_start_form()? {
+            Some(Node::Map(_, entries, _, _)) => entries,
+            Some(x) => return Err(SyntaxError::InvalidDataReaders(x.span())),
+            None => return Ok(())
+        };
+
+        for entry in entries {
+            match entry {
+                Node::KeyValue(tag, function) => match (*tag, *function) {
+                    (tag @ Node::Symbol(_, Some(_), _, _), function @ Node::Symbol(_, _, _, _)) => {
+                        self.register_tag_reader(&tag.to_string(), move |node| match node {
+                            Node::Tagged(_, form) => {
+                                let span = form.span();
+                                Ok(list_node(span, vec![function.clone(), list_node(span, vec![name_node(span, None, "quote"), *form])]))
+                            },
+                            x => Ok(x)
+                        })
+                    },
+                    (x, _) => return Err(SyntaxError::InvalidDataReaders(x.span()))
+                },
+                x => return Err(SyntaxError::InvalidDataReaders(x.span()))
+            }
+        }
+        Ok(())
+    }
+
+    /* Reads new input with the same namespace, features and tag readers, generated names keep counting */
+    fn set_lexer(&mut self, lexer: LexicalAnalyzer<'a>) {
+        self.lexer = lexer;
+        self.symbol = Ok(Symbols::EOF(Position::default(), Position::default()));
+        self.in_anonymous_fn = false
+    }
+
     fn advance(&mut self) {
         self.symbol = self.lexer.get_symbol()
     }
 
-    fn parse_start_unit(&mut self) -> Result<Node, Box<String>> {
+    fn parse_start_unit(&mut self) -> Result<Node, SyntaxError> {
+        let start = self.lexer.position();
         self.advance();
-        self.parse_unit()
+        self.parse_unit(start)
+    }
+
+    /* Reads the whole input even when it has syntax errors, for editors.
+     * Broken forms read as Node::Error and every error found is returned with the unit.
+     */
+    fn parse_start_unit_recovering(&mut self) -> (Node, Vec<SyntaxError>) {
+        self.recovering = true;
+        let unit = match self.parse_start_unit() {
+            Ok(unit) => unit,
+            Err(error) => {
+                let span = error.span();
+                self.diagnostics.push(error);
+                Node::Unit(span, vec![Node::Error(span)])
+            }
+        };
+        self.recovering = false;
+        (unit, std::mem::take(&mut self.diagnostics))
+    }
+
+    /* When recovering the error is recorded and the broken form reads as Node::Error.
+     * Collections end at their own closing delimiter, so the next token is where the following form starts.
+     */
+    fn recover(&mut self, error: SyntaxError) -> Result<Node, SyntaxError> {
+        if !self.recovering {
+            return Err(error)
+        }
+        if self.symbol.as_ref().err() == Some(&error) {
+            self.advance()
+        }
+        let span = error.span();
+        self.diagnostics.push(error);
+        Ok(Node::Error(span))
     }
 
-    fn parse_unit(&mut self) -> Result<Node, Box<String>> {
+    fn parse_unit(&mut self, start: Position) -> Result<Node, SyntaxError> {
         let mut nodes : Vec::<Node> = Vec::new();
-        
+
+        loop {
+            let symbol = match self.symbol.clone() {
+                Ok(symbol) => symbol,
+                Err(error) => {
+                    nodes.push(self.recover(error)?);
+                    continue
+                }
+            };
+            let result = match symbol {
+                Symbols::EOF(_, end) => {
+                    return Ok(Node::Unit(Span::new(start, end), nodes))
+                },
+                Symbols::Discard(_, _) => match self.parse_discard() {
+                    Ok(_) => continue,
+                    Err(error) => Err(error)
+                },
+                Symbols::ReaderConditional(_, _) | Symbols::ReaderConditionalSplicing(_, _) => match self.parse_reader_conditional(false) {
+                    Ok(x) => {
+                        nodes.extend(x);
+                        continue
+                    },
+                    Err(error) => Err(error)
+                },
+                /* Any form can be at top level, like in EDN documents, atoms read as typed literal nodes */
+                _ => self.parse_form()
+            };
+            match result {
+                Ok(node) => nodes.push(node),
+                Err(error) => nodes.push(self.recover(error)?)
+            }
+        }
+    }
+
+    /* Read a single form without requiring the rest of the input to be complete, None at end of file */
+    fn parse_start_form(&mut self) -> Result<Option<Node>, SyntaxError> {
+        self.advance();
         loop {
             match self.symbol.clone()? {
-                Symbols::EOF => {
-                    return Ok(Node::Unit(nodes))
+                Symbols::EOF(_, _) => return Ok(None),
+                Symbols::Discard(_, _) => self.parse_discard()?,
+                Symbols::ReaderConditional(_, _) | Symbols::ReaderConditionalSplicing(_, _) => {
+                    if let Some(x) = self.parse_reader_conditional(false)?.pop() {
+                        return Ok(Some(x))
+                    }
                 },
-                Symbols::LeftParen(_, _) => {
-                    nodes.push(self.parse_list()?)
+                _ => return self.parse_form().map(Some)
+            }
+        }
+    }
+
+    fn parse_form(&mut self) -> Result<Node, SyntaxError> {
+        match self.symbol.clone()? {
+            Symbols::LeftParen(_, _) => self.parse_list(),
+            Symbols::LeftBracket(_, _) => self.parse_vector(),
+            Symbols::LeftCurly(_, _) => self.parse_map(),
+            x @ Symbols::EOF(_, _) => Err(SyntaxError::UnexpectedEndOfFile(x.span())),
+            x @ (Symbols::RightParen(_, _) | Symbols::RightBracket(_, _) | Symbols::RightCurly(_, _)) => {
+                Err(SyntaxError::UnexpectedClosingDelimiter(x.span()))
+            },
+            Symbols::Hash(_, _) => self.parse_dispatch(),
+            Symbols::Quote(_, _) | Symbols::SyntaxQuote(_, _) | Symbols::Unquote(_, _) | Symbols::UnquoteSplicing(_, _) => self.parse_quote(),
+            Symbols::Discard(_, _) => {
+                self.parse_discard()?;
+                self.parse_form()
+            },
+            Symbols::Caret(_, _) => self.parse_meta(),
+            Symbols::ReaderConditional(_, _) | Symbols::ReaderConditionalSplicing(_, _) => {
+                match self.parse_reader_conditional(false)?.pop() {
+                    Some(x) => Ok(x),
+                    None => self.parse_form()
+                }
+            },
+            x => match Node::literal(x.clone()) {
+                Some(node) => {
+                    self.advance();
+                    Ok(node)
                 },
-                Symbols::LeftBracket(_, _) => {
-                    nodes.push(self.parse_vector()?)
+                None => Err(SyntaxError::UnexpectedToken(x.span(), Box::new(x.to_string())))
+            }
+        }
+    }
+
+    /* Collect forms up to the closing delimiter, the lexer has already checked that it matches the opening one */
+    fn parse_elements(&mut self) -> Result<(Vec<Node>, Symbols), SyntaxError> {
+        let mut nodes : Vec::<Node> = Vec::new();
+
+        loop {
+            let symbol = match self.symbol.clone() {
+                Ok(symbol) => symbol,
+                Err(error) => {
+                    nodes.push(self.recover(error)?);
+                    continue
+                }
+            };
+            let result = match symbol {
+                Symbols::RightParen(_, _) | Symbols::RightBracket(_, _) | Symbols::RightCurly(_, _) => {
+                    self.advance();
+                    return Ok((nodes, symbol))
                 },
-                Symbols::LeftCurly(_, _) => {
-                    nodes.push(self.parse_map()?)
+                /* When recovering the collection ends at the end of file */
+                x @ Symbols::EOF(_, _) => {
+                    let error = self.lexer.missing_closing_delimiter(x.span());
+                    self.recover(error)?;
+                    return Ok((nodes, x))
                 },
-                _ => return Err(Box::new("Syntax error!".to_string()))
+                Symbols::Discard(_, _) => self.parse_discard().map(|_| None),
+                Symbols::ReaderConditional(_, _) | Symbols::ReaderConditionalSplicing(_, _) => self.parse_reader_conditional(true).map(|x| {
+                    nodes.extend(x);
+                    None
+                }),
+                _ => self.parse_form().map(Some)
+            };
+            match result {
+                Ok(Some(node)) => nodes.push(node),
+                Ok(None) => (),
+                Err(error) => nodes.push(self.recover(error)?)
             }
         }
     }
 
-    fn parse_list(&mut self) -> Result<Node, Box<String>> {
-        todo!()
+    /* Collections remember where they start in their metadata, like the Clojure reader does for the compiler */
+    fn parse_list(&mut self) -> Result<Node, SyntaxError> {
+        let start = self.symbol.clone()?;
+        self.advance();
+        let (nodes, end) = self.parse_elements()?;
+        let metadata = position_metadata(start.span().start);
+        Ok(Node::List(start, nodes, end, metadata))
     }
 
-    fn parse_vector(&mut self) -> Result<Node, Box<String>> {
-        todo!()
+    fn parse_vector(&mut self) -> Result<Node, SyntaxError> {
+        let start = self.symbol.clone()?;
+        self.advance();
+        let (nodes, end) = self.parse_elements()?;
+        let metadata = position_metadata(start.span().start);
+        Ok(Node::Vec(start, nodes, end, metadata))
     }
 
-    fn parse_map(&mut self) -> Result<Node, Box<String>> {
-        todo!()
+    fn parse_map(&mut self) -> Result<Node, SyntaxError> {
+        let start = self.symbol.clone()?;
+        self.advance();
+        let (nodes, end) = self.parse_elements()?;
+
+        if nodes.len() % 2 != 0 {
+            return Err(SyntaxError::OddNumberOfMapForms(start.span().merge(&end.span())))
+        }
+
+        check_duplicates(nodes.iter().step_by(2))?;
+
+        let mut entries : Vec::<Node> = Vec::new();
+        let mut iter = nodes.into_iter();
+        while let (Some(key), Some(value)) = (iter.next(), iter.next()) {
+            entries.push(Node::KeyValue(Box::new(key), Box::new(value)))
+        }
+
+        let metadata = position_metadata(start.span().start);
+        Ok(Node::Map(start, entries, end, metadata))
+    }
+
+    /* Handle forms starting with '#', the dispatch character must be directly followed by the form */
+    fn parse_dispatch(&mut self) -> Result<Node, SyntaxError> {
+        let (start, end) = match self.symbol.clone()? {
+            Symbols::Hash(start, end) => (start, end),
+            x => return Err(SyntaxError::UnexpectedToken(x.span(), Box::new(x.to_string())))
+        };
+        self.advance();
+
+        match self.symbol.clone()? {
+            Symbols::LeftCurly(x, _) if x == end => self.parse_set(start),
+            Symbols::LeftParen(x, _) if x == end => self.parse_anonymous_fn(start),
+            Symbols::Quote(x, _) if x == end => self.parse_var_quote(start),
+            Symbols::LiteralName(x, _, _, _) if x == end => self.parse_tagged(start),
+            _ => Err(SyntaxError::MissingDispatchForm(Span::new(start, end)))
+        }
     }
 
+    fn parse_set(&mut self, start: Position) -> Result<Node, SyntaxError> {
+        let open = match self.symbol.clone()? {
+            Symbols::LeftCurly(_, end) => Symbols::LeftCurly(start, end),
+            x => return Err(SyntaxError::UnexpectedToken(x.span(), Box::new(x.to_string())))
+        };
+        self.advance();
+        let (nodes, end) = self.parse_elements()?;
+
+        check_duplicates(nodes.iter())?;
+        Ok(Node::Set(open, nodes, end, position_metadata(start)))
+    }
 
+    /* #_ reads the next form and drops it, so #_ #_ a b drops both a and b */
+    fn parse_discard(&mut self) -> Result<(), SyntaxError> {
+        self.advance();
+        self.parse_form().map(|_| ())
+    }
+
+    /* #(f % %2 %&) reads as (fn* [p1__1# p2__2# & rest__3#] (f p1__1# p2__2# rest__3#)) */
+    fn parse_anonymous_fn(&mut self, start: Position) -> Result<Node, SyntaxError> {
+        if self.in_anonymous_fn {
+            return Err(SyntaxError::NestedAnonymousFn(Span::new(start, self.symbol.clone()?.span().end)))
+        }
+
+        self.in_anonymous_fn = true;
+        let body = self.parse_list();
+        self.in_anonymous_fn = false;
+        let body = body?;
+
+        let mut count = 0;
+        let mut rest = false;
+        collect_args(&body, &mut count, &mut rest)?;
+
+        let span = Span::new(start, body.span().end);
+        let mut args : Vec::<(String, Node)> = Vec::new();
+        for index in 1..=count {
+            let name = format!("p{}__{}#", index, self.next_gensym_id());
+            args.push((format!("%{}", index), name_node(span, None, &name)))
+        }
+        let mut params : Vec::<Node> = args.iter().map(|(_, x)| x.clone()).collect();
+        if rest {
+            let name = name_node(span, None, &format!("rest__{}#", self.next_gensym_id()));
+            params.push(name_node(span, None, "&"));
+            params.push(name.clone());
+            args.push(("%&".to_string(), name))
+        }
+
+        let params = Node::Vec(Symbols::LeftBracket(span.start, span.start), params, Symbols::RightBracket(span.start, span.start), position_metadata(span.start));
+        Ok(list_node(span, vec![name_node(span, None, "fn*"), params, replace_args(&body, &args)]))
+    }
+
+    /* #'x reads as (var x) */
+    fn parse_var_quote(&mut self, start: Position) -> Result<Node, SyntaxError> {
+        match self.symbol.clone()? {
+            Symbols::Quote(_, _) => self.advance(),
+            x => return Err(SyntaxError::UnexpectedToken(x.span(), Box::new(x.to_string())))
+        };
+        let form = self.parse_form()?;
+
+        let span = Span::new(start, form.span().end);
+        Ok(list_node(span, vec![name_node(span, None, "var"), form]))
+    }
+
+    /* Reader macros for quoting, 'x reads as (quote x) and ~x as (clojure.core/unquote x) */
+    fn parse_quote(&mut self) -> Result<Node, SyntaxError> {
+        let symbol = self.symbol.clone()?;
+        self.advance();
+        let form = self.parse_form()?;
+
+        let (namespace, name) = match symbol {
+            Symbols::Quote(_, _) => (None, "quote"),
+            Symbols::Unquote(_, _) => (Some("clojure.core"), "unquote"),
+            Symbols::UnquoteSplicing(_, _) => (Some("clojure.core"), "unquote-splicing"),
+            Symbols::SyntaxQuote(_, _) => return self.syntax_quote(&form, &mut HashMap::new()),
+            x => return Err(SyntaxError::UnexpectedToken(x.span(), Box::new(x.to_string())))
+        };
+
+        let span = symbol.span().merge(&form.span());
+        Ok(list_node(span, vec![name_node(span, namespace, name), form]))
+    }
+
+    /* ^:private x, ^String x and ^{:doc "..."} x put a metadata map in the slot of x, with several ^ the outer entries win.
+     * The map also gets the :line and :column where x starts.
+     */
+    fn parse_meta(&mut self) -> Result<Node, SyntaxError> {
+        self.advance();
+        let metadata = metadata_map(self.parse_form()?)?;
+        let mut form = self.parse_form()?;
+        let start = form.span().start;
+
+        match form.metadata_mut() {
+            Some(slot) => {
+                let merged = match slot.take() {
+                    Some(inner) => merge_metadata(*inner, metadata),
+                    None => metadata
+                };
+                *slot = Some(Box::new(with_position(Some(merged), start)));
+                Ok(form)
+            },
+            None => Err(SyntaxError::InvalidMetadataTarget(form.span()))
+        }
+    }
+
+    /* #?(:clj a :native b) reads as the first branch with an active feature, or as nothing when no branch matches.
+     * #?@ splices the items of the selected list or vector into the surrounding collection.
+     */
+    fn parse_reader_conditional(&mut self, in_collection: bool) -> Result<Vec<Node>, SyntaxError> {
+        let start = self.symbol.clone()?;
+        let splicing = matches!(start, Symbols::ReaderConditionalSplicing(_, _));
+        self.advance();
+
+        /* Branches for other platforms may use tags this reader does not know, tags are read after a branch is selected */
+        let suppress_tags = self.suppress_tags;
+        self.suppress_tags = true;
+        let form = self.parse_form();
+        self.suppress_tags = suppress_tags;
+        let form = form?;
+
+        let branches = match &form {
+            Node::List(_, nodes, _, _) => nodes.clone(),
+            x => return Err(SyntaxError::ReaderConditionalNotList(x.span()))
+        };
+        if branches.len() % 2 != 0 {
+            return Err(SyntaxError::ReaderConditionalOddForms(form.span()))
+        }
+        if let Some(x) = branches.iter().step_by(2).find(|x| !matches!(x, Node::Keyword(_, None, _))) {
+            return Err(SyntaxError::FeatureNotKeyword(x.span()))
+        }
+
+        if self.preserve_conditionals {
+            return Ok(vec![Node::Conditional(start, Box::new(form))])
+        }
+        if splicing && !in_collection {
+            return Err(SyntaxError::SplicingNotInCollection(start.span()))
+        }
+
+        let selected = branches.chunks(2).find(|x| match &x[0] {
+            Node::Keyword(_, None, name) => name.as_str() == "default" || self.features.contains(name),
+            _ => false
+        });
+
+        let selected = match (selected, suppress_tags) {
+            (Some([_, form]), false) => Some(self.read_tags(form)?),
+            (Some([_, form]), true) => Some(form.clone()),
+            _ => None
+        };
+
+        match (selected, splicing) {
+            (None, _) => Ok(vec![]),
+            (Some(form), false) => Ok(vec![form]),
+            (Some(Node::List(_, nodes, _, _) | Node::Vec(_, nodes, _, _)), true) => Ok(nodes),
+            (Some(x), _) => Err(SyntaxError::SplicedFormNotSequential(x.span()))
+        }
+    }
+
+    /* #inst "2024-01-01" gives the tag symbol, starting at '#', and the form that follows it to the reader for the tag */
+    fn parse_tagged(&mut self, start: Position) -> Result<Node, SyntaxError> {
+        let tag = match self.symbol.clone()? {
+            Symbols::LiteralName(_, end, namespace, name) => Symbols::LiteralName(start, end, namespace, name),
+            x => return Err(SyntaxError::UnexpectedToken(x.span(), Box::new(x.to_string())))
+        };
+        self.advance();
+        let form = self.parse_form()?;
+
+        match self.suppress_tags {
+            true => Ok(Node::Tagged(tag, Box::new(form))),
+            false => self.read_tagged(Node::Tagged(tag, Box::new(form)))
+        }
+    }
+
+    fn read_tagged(&self, node: Node) -> Result<Node, SyntaxError> {
+        let (tag, span) = match &node {
+            Node::Tagged(tag, _) => (tag.to_string(), node.span()),
+            _ => return Ok(node)
+        };
+
+        match self.tag_readers.get(&tag) {
+            Some(reader) => reader(node).map_err(|x| SyntaxError::InvalidTaggedLiteral(span, Box::new(x))),
+            None => Err(SyntaxError::UnknownTag(span, Box::new(tag)))
+        }
+    }
+
+    /* Runs the tag readers on a form read while tags were suppressed, innermost tags first */
+    fn read_tags(&self, node: &Node) -> Result<Node, SyntaxError> {
+        let read_all = |nodes: &[Node]| nodes.iter().map(|x| self.read_tags(x)).collect::<Result<Vec<Node>, SyntaxError>>();
+        let mut node = match node {
+            Node::List(start, nodes, end, metadata) => Node::List(start.clone(), read_all(nodes)?, end.clone(), metadata.clone()),
+            Node::Vec(start, nodes, end, metadata) => Node::Vec(start.clone(), read_all(nodes)?, end.clone(), metadata.clone()),
+            Node::Map(start, nodes, end, metadata) => Node::Map(start.clone(), read_all(nodes)?, end.clone(), metadata.clone()),
+            Node::Set(start, nodes, end, metadata) => Node::Set(start.clone(), read_all(nodes)?, end.clone(), metadata.clone()),
+            Node::KeyValue(key, value) => Node::KeyValue(Box::new(self.read_tags(key)?), Box::new(self.read_tags(value)?)),
+            Node::Tagged(tag, form) => return self.read_tagged(Node::Tagged(tag.clone(), Box::new(self.read_tags(form)?))),
+            _ => node.clone()
+        };
+        if let Some(Some(metadata)) = node.metadata_mut() {
+            let read = self.read_tags(metadata)?;
+            **metadata = read
+        }
+        Ok(node)
+    }
+
+    /* Expand a syntax quoted form into the code that builds it, following the Clojure reader */
+    fn syntax_quote(&mut self, node: &Node, gensyms: &mut HashMap<String, String>) -> Result<Node, SyntaxError> {
+        let span = node.span();
+
+        /* Metadata is kept by wrapping the expansion in (clojure.core/with-meta form meta), without the reader positions */
+        if let Some(metadata) = source_metadata(node) {
+            return Ok(list_node(span, vec![
+                name_node(span, Some("clojure.core"), "with-meta"),
+                self.syntax_quote(&node.without_meta(), gensyms)?,
+                self.syntax_quote(&metadata, gensyms)?
+            ]))
+        }
+
+        match node {
+            Node::Symbol(symbol_span, namespace, name, _) => {
+                let symbol = match namespace {
+                    None if SYNTAX_QUOTE_SPECIALS.contains(&name.as_str()) => node.without_meta(),
+                    None if name.len() > 1 && name.ends_with('#') => {
+                        let id = gensyms.entry(name.to_string()).or_insert_with(|| {
+                            format!("{}__{}__auto__", &name[..name.len() - 1], self.next_gensym_id())
+                        });
+                        Node::Symbol(*symbol_span, None, Box::new(id.clone()), None)
+                    },
+                    /* Class names, constructors like Foo. and methods like .foo are left alone */
+                    None if name.contains('.') => node.without_meta(),
+                    None => Node::Symbol(*symbol_span, Some(self.namespace.clone()), name.clone(), None),
+                    Some(_) => node.without_meta()
+                };
+                Ok(list_node(span, vec![name_node(span, None, "quote"), symbol]))
+            },
+            Node::List(_, nodes, _, _) => {
+                match unquoted(node) {
+                    Some(("unquote", form)) => Ok(form.clone()),
+                    Some(_) => Err(SyntaxError::UnquoteSplicingNotInList(span)),
+                    None if nodes.is_empty() => Ok(list_node(span, vec![name_node(span, Some("clojure.core"), "list")])),
+                    None => self.syntax_quote_elements(nodes, gensyms)
+                }
+            },
+            Node::Vec(_, nodes, _, _) => Ok(list_node(span, vec![
+                name_node(span, Some("clojure.core"), "apply"),
+                name_node(span, Some("clojure.core"), "vector"),
+                self.syntax_quote_elements(nodes, gensyms)?
+            ])),
+            Node::Map(_, entries, _, _) => {
+                let mut nodes : Vec::<Node> = Vec::new();
+                for entry in entries {
+                    if let Node::KeyValue(key, value) = entry {
+                        nodes.push(*key.clone());
+                        nodes.push(*value.clone())
+                    }
+                }
+                Ok(list_node(span, vec![
+                    name_node(span, Some("clojure.core"), "apply"),
+                    name_node(span, Some("clojure.core"), "hash-map"),
+                    self.syntax_quote_elements(&nodes, gensyms)?
+                ]))
+            },
+            Node::Set(_, nodes, _, _) => Ok(list_node(span, vec![
+                name_node(span, Some("clojure.core"), "apply"),
+                name_node(span, Some("clojure.core"), "hash-set"),
+                self.syntax_quote_elements(nodes, gensyms)?
+            ])),
+            /* Keywords, numbers, strings and characters evaluate to themselves */
+            _ => Ok(node.clone())
+        }
+    }
+
+    /* Builds (clojure.core/seq (clojure.core/concat ...)) where unquote splicing inserts its form directly */
+    fn syntax_quote_elements(&mut self, nodes: &[Node], gensyms: &mut HashMap<String, String>) -> Result<Node, SyntaxError> {
+        let span = match (nodes.first(), nodes.last()) {
+            (Some(first), Some(last)) => first.span().merge(&last.span()),
+            _ => Span::new(Position::default(), Position::default())
+        };
+
+        let mut parts = vec![name_node(span, Some("clojure.core"), "concat")];
+        for node in nodes {
+            let part = match unquoted(node) {
+                Some(("unquote", form)) => list_node(node.span(), vec![name_node(node.span(), Some("clojure.core"), "list"), form.clone()]),
+                Some((_, form)) => form.clone(),
+                None => list_node(node.span(), vec![name_node(node.span(), Some("clojure.core"), "list"), self.syntax_quote(node, gensyms)?])
+            };
+            parts.push(part)
+        }
+
+        Ok(list_node(span, vec![name_node(span, Some("clojure.core"), "seq"), list_node(span, parts)]))
+    }
+
+    /* Numbers generated names, counted per parser so the same input always reads the same */
+    fn next_gensym_id(&mut self) -> u32 {
+        let id = self.gensym_id;
+        self.gensym_id += 1;
+        id
+    }
+}
+
+/* The readers every parser starts with, #inst and #uuid check their string and read as themselves */
+fn default_tag_readers() -> HashMap<String, TagReader> {
+    let mut readers : HashMap<String, TagReader> = HashMap::new();
+    readers.insert("inst".to_string(), Rc::new(|node: Node| match tagged_string(&node) {
+        Some(text) if is_timestamp(text) => Ok(node),
+        Some(text) => Err(format!("Unrecognized date/time syntax: {}", text)),
+        None => Err("Instance literal expects a string for its timestamp.".to_string())
+    }));
+    readers.insert("uuid".to_string(), Rc::new(|node: Node| match tagged_string(&node) {
+        Some(text) if is_uuid(text) => Ok(node),
+        Some(text) => Err(format!("Invalid UUID string: {}", text)),
+        None => Err("UUID literal expects a string as its representation.".to_string())
+    }));
+    readers
+}
+
+fn tagged_string(node: &Node) -> Option<&str> {
+    match node {
+        Node::Tagged(_, form) => match form.as_ref() {
+            Node::String(_, text) => Some(text.as_str()),
+            _ => None
+        },
+        _ => None
+    }
+}
+
+/* RFC 3339 timestamps where everything after the year is optional, like 2024, 2024-01-01T10:30 or 2024-01-01T10:30:00.5+01:00 */
+fn is_timestamp(text: &str) -> bool {
+    let (time, offset) = match text.find(['Z', '+']).or_else(|| text.get(10..).and_then(|x| x.find('-')).map(|x| x + 10)) {
+        Some(index) => text.split_at(index),
+        None => (text, "")
+    };
+    let (time, fraction) = match time.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (time, None)
+    };
+    let number = |text: &str, low: u32, high: u32| text.len() == 2 && text.parse::<u32>().is_ok_and(|x| x >= low && x <= high);
+
+    let (date, clock) = match time.split_once('T') {
+        Some((date, clock)) => (date, Some(clock)),
+        None => (time, None)
+    };
+    let date : Vec<&str> = date.split('-').collect();
+    let year = match date[0].parse::<u32>() {
+        Ok(year) if date[0].len() == 4 => year,
+        _ => return false
+    };
+    let month = date.get(1).copied().unwrap_or("01");
+    let days = match month {
+        "02" if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        "02" => 28,
+        "04" | "06" | "09" | "11" => 30,
+        _ => 31
+    };
+    let date_valid = date.len() <= 3 && number(month, 1, 12) && number(date.get(2).copied().unwrap_or("01"), 1, days);
+
+    let clock : Vec<&str> = clock.map(|x| x.split(':').collect()).unwrap_or_default();
+    let clock_valid = match clock[..] {
+        [] => fraction.is_none(),
+        [hour] => date.len() == 3 && number(hour, 0, 23) && fraction.is_none(),
+        [hour, minute] => date.len() == 3 && number(hour, 0, 23) && number(minute, 0, 59) && fraction.is_none(),
+        [hour, minute, second] => date.len() == 3 && number(hour, 0, 23) && number(minute, 0, 59) && number(second, 0, 60),
+        _ => false
+    };
+    let fraction_valid = fraction.is_none_or(|x| !x.is_empty() && x.chars().all(|x| x.is_ascii_digit()));
+    let offset_valid = match offset.split_at_checked(1) {
+        None => true,
+        Some(("Z", "")) => true,
+        Some(("+" | "-", zone)) => matches!(zone.split_once(':'), Some((hour, minute)) if number(hour, 0, 23) && number(minute, 0, 59)),
+        _ => false
+    };
+
+    date_valid && clock_valid && fraction_valid && offset_valid
+}
+
+/* Five groups of 8-4-4-4-12 hexadecimal digits */
+fn is_uuid(text: &str) -> bool {
+    let groups : Vec<&str> = text.split('-').collect();
+    groups.iter().map(|x| x.len()).eq([8, 4, 4, 4, 12]) && groups.iter().all(|x| x.chars().all(|x| x.is_ascii_hexdigit()))
+}
+
+/* Symbol made up by the reader, placed at the start of the span it belongs to */
+fn name_node(span: Span, namespace: Option<&str>, name: &str) -> Node {
+    Node::Symbol(Span::new(span.start, span.start), namespace.map(|x| Box::new(x.to_string())), Box::new(name.to_string()), None)
+}
+
+/* List made up by the reader covering the span it was expanded from, positioned like a read list */
+fn list_node(span: Span, nodes: Vec<Node>) -> Node {
+    Node::List(Symbols::LeftParen(span.start, span.start), nodes, Symbols::RightParen(span.end, span.end), position_metadata(span.start))
+}
+
+/* Set elements and map keys must be distinct values, the error is on the second one */
+fn check_duplicates<'n>(nodes: impl Iterator<Item = &'n Node>) -> Result<(), SyntaxError> {
+    let mut keys : HashSet::<String> = HashSet::new();
+    for node in nodes {
+        if let Some(key) = node.value_key() {
+            if !keys.insert(key) {
+                return Err(SyntaxError::DuplicateKey(node.span(), Box::new(node.without_meta().to_string())))
+            }
+        }
+    }
+    Ok(())
+}
+
+/* Expands the shorthand forms ^:key, ^Tag and ^[types] into a metadata map */
+fn metadata_map(node: Node) -> Result<Node, SyntaxError> {
+    let span = node.span();
+    let keyword = |name: &str| Node::Keyword(Span::new(span.start, span.start), None, Box::new(name.to_string()));
+    let entry = |key: Node, value: Node| {
+        Node::Map(Symbols::LeftCurly(span.start, span.start), vec![Node::KeyValue(Box::new(key), Box::new(value))], Symbols::RightCurly(span.end, span.end), None)
+    };
+
+    match node {
+        Node::Map(_, _, _, _) => Ok(node),
+        Node::Keyword(_, _, _) | Node::AutoResolvedKeyword(_, _, _) => Ok(entry(node, Node::Bool(Span::new(span.end, span.end), true))),
+        Node::Symbol(_, _, _, _) | Node::String(_, _) => Ok(entry(keyword("tag"), node)),
+        Node::Vec(_, _, _, _) => Ok(entry(keyword("param-tags"), node)),
+        x => Err(SyntaxError::InvalidMetadata(x.span()))
+    }
+}
+
+/* Adds the outer entries to the inner metadata map, replacing entries with the same key */
+fn merge_metadata(inner: Node, outer: Node) -> Node {
+    match (inner, outer) {
+        (Node::Map(_, mut entries, _, _), Node::Map(start, outer_entries, end, _)) => {
+            let key = |x: &Node| match x {
+                Node::KeyValue(key, _) => key.to_string(),
+                _ => x.to_string()
+            };
+            for entry in outer_entries {
+                match entries.iter().position(|x| key(x) == key(&entry)) {
+                    Some(index) => entries[index] = entry,
+                    None => entries.push(entry)
+                }
+            }
+            Node::Map(start, entries, end, None)
+        },
+        (_, outer) => outer
+    }
+}
+
+/* Adds the :line and :column of the form start to its metadata map, entries already in the map win */
+fn with_position(metadata: Option<Node>, start: Position) -> Node {
+    let span = Span::new(start, start);
+    let entry = |name: &str, value: u32| {
+        Node::KeyValue(Box::new(Node::Keyword(span, None, Box::new(name.to_string()))), Box::new(Node::Long(span, value as i64)))
+    };
+    let position = Node::Map(
+        Symbols::LeftCurly(start, start), vec![entry("line", start.line), entry("column", start.column)], Symbols::RightCurly(start, start), None
+    );
+    match metadata {
+        Some(metadata) => merge_metadata(position, metadata),
+        None => position
+    }
+}
+
+/* Metadata slot of a collection holding only the :line and :column of its start */
+fn position_metadata(start: Position) -> Option<Box<Node>> {
+    Some(Box::new(with_position(None, start)))
+}
+
+/* The metadata written in the source, without the positions added by the reader, None when nothing is left */
+fn source_metadata(node: &Node) -> Option<Node> {
+    match node.metadata() {
+        Some(Node::Map(start, entries, end, _)) => {
+            let entries : Vec::<Node> = entries.iter().filter(|x| match x {
+                Node::KeyValue(key, _) => !matches!(key.as_ref(), Node::Keyword(_, None, name) if name.as_str() == "line" || name.as_str() == "column"),
+                _ => true
+            }).cloned().collect();
+            match entries.is_empty() {
+                true => None,
+                false => Some(Node::Map(start.clone(), entries, end.clone(), None))
+            }
+        },
+        _ => None
+    }
+}
+
+/* Finds the highest numbered % argument and whether %& is used, % counts as %1 */
+fn collect_args(node: &Node, count: &mut usize, rest: &mut bool) -> Result<(), SyntaxError> {
+    if let Some(metadata) = node.metadata() {
+        collect_args(metadata, count, rest)?
+    }
+    match node {
+        Node::Symbol(_, None, name, _) if name.starts_with('%') => {
+            match &name[1..] {
+                "" => *count = (*count).max(1),
+                "&" => *rest = true,
+                x => match x.parse::<usize>() {
+                    Ok(index) if index > 0 => *count = (*count).max(index),
+                    _ => return Err(SyntaxError::InvalidArgLiteral(node.span(), name.clone()))
+                }
+            }
+        },
+        Node::List(_, nodes, _, _) | Node::Vec(_, nodes, _, _) | Node::Map(_, nodes, _, _) | Node::Set(_, nodes, _, _) => {
+            for node in nodes {
+                collect_args(node, count, rest)?
+            }
+        },
+        Node::KeyValue(key, value) => {
+            collect_args(key, count, rest)?;
+            collect_args(value, count, rest)?
+        },
+        Node::Tagged(_, form) => collect_args(form, count, rest)?,
+        _ => ()
+    }
+    Ok(())
+}
+
+/* Replaces % arguments with the generated parameter names */
+fn replace_args(node: &Node, args: &[(String, Node)]) -> Node {
+    let replace_all = |nodes: &[Node]| nodes.iter().map(|x| replace_args(x, args)).collect::<Vec<Node>>();
+    let metadata = node.metadata().map(|x| Box::new(replace_args(x, args)));
+    match node {
+        Node::Symbol(_, None, name, _) if name.starts_with('%') => {
+            let key = if name.as_str() == "%" { "%1" } else { name.as_str() };
+            match args.iter().find(|(x, _)| x == key) {
+                Some((_, Node::Symbol(_, _, replacement, _))) => {
+                    Node::Symbol(node.span(), None, replacement.clone(), metadata)
+                },
+                _ => node.clone()
+            }
+        },
+        Node::Symbol(span, namespace, name, _) => Node::Symbol(*span, namespace.clone(), name.clone(), metadata),
+        Node::List(start, nodes, end, _) => Node::List(start.clone(), replace_all(nodes), end.clone(), metadata),
+        Node::Vec(start, nodes, end, _) => Node::Vec(start.clone(), replace_all(nodes), end.clone(), metadata),
+        Node::Map(start, nodes, end, _) => Node::Map(start.clone(), replace_all(nodes), end.clone(), metadata),
+        Node::Set(start, nodes, end, _) => Node::Set(start.clone(), replace_all(nodes), end.clone(), metadata),
+        Node::KeyValue(key, value) => Node::KeyValue(Box::new(replace_args(key, args)), Box::new(replace_args(value, args))),
+        Node::Tagged(tag, form) => Node::Tagged(tag.clone(), Box::new(replace_args(form, args))),
+        _ => node.clone()
+    }
+}
+
+/* Matches (clojure.core/unquote x) and (clojure.core/unquote-splicing x), giving the name and x */
+fn unquoted(node: &Node) -> Option<(&str, &Node)> {
+    match node {
+        Node::List(_, nodes, _, _) => match &nodes[..] {
+            [Node::Symbol(_, Some(namespace), name, _), form]
+                if namespace.as_str() == "clojure.core" && (name.as_str() == "unquote" || name.as_str() == "unquote-splicing") => {
+                Some((name.as_str(), form))
+            },
+            _ => None
+        },
+        _ => None
+    }
+}
+
+/* Prints the form back as Clojure source, positions are ignored so equal forms give equal text.
+ * Metadata is printed as well, except :line and :column which the reader adds to lists itself.
+ */
+impl std::fmt::Display for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        fn join(nodes: &[Node], separator: &str) -> String {
+            nodes.iter().map(|x| x.to_string()).collect::<Vec<String>>().join(separator)
+        }
+
+        if let Some(metadata) = source_metadata(self) {
+            write!(f, "^{} ", metadata)?
+        }
+
+        match self {
+            Node::Unit(_, nodes) => write!(f, "{}", join(nodes, "\n")),
+            Node::List(_, nodes, _, _) => write!(f, "({})", join(nodes, " ")),
+            Node::Vec(_, nodes, _, _) => write!(f, "[{}]", join(nodes, " ")),
+            Node::Map(_, nodes, _, _) => write!(f, "{{{}}}", join(nodes, ", ")),
+            Node::Set(_, nodes, _, _) => write!(f, "#{{{}}}", join(nodes, " ")),
+            Node::KeyValue(key, value) => write!(f, "{} {}", key, value),
+            Node::Conditional(start, form) => write!(f, "{}{}", start, form),
+            Node::Tagged(tag, form) => write!(f, "#{} {}", tag, form),
+            Node::Error(_) => write!(f, "<error>"),
+            x => match x.token() {
+                Some(symbol) => write!(f, "{}", symbol),
+                None => Ok(())
+            }
+        }
+    }
 }
 
 
@@ -89,9 +1173,16 @@ impl ExpressionParserMethods for ExpressionParser {
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::{expression_parser::ExpressionParser, lexical_analyzer::{LexicalAnalyzer, LexicalAnalyzerMethods}};
+    use crate::parser::{expression_parser::ExpressionParser, lexical_analyzer::{LexicalAnalyzer, LexicalAnalyzerMethods, Symbols}};
 
-    use super::{ExpressionParserMethods, Node };
+    use super::{ExpressionParserMethods, Node, with_position };
+    use crate::parser::source_position::{Position, Span};
+    use crate::parser::syntax_error::SyntaxError;
+
+    /* Position on the first line of the default file */
+    fn p(offset: u32) -> Position {
+        Position::new(0, 1, offset + 1, offset)
+    }
 
 
     #[test]
@@ -100,16 +1191,603 @@ mod tests {
         let mut parser = ExpressionParser::new(lexer);
         let res = parser.parse_start_unit();
 
-        match res {
-            Ok(x) => {
-                match x {
-                    Node::Unit(lst) => {
-                        assert_eq!(lst.len(), (0 as usize))
+        assert_eq!(res, Ok(Node::Unit(Span::new(p(0), p(27)), vec![])))
+    }
+
+    #[test]
+    fn atoms_at_top_level() {
+        let lexer = LexicalAnalyzer::new(":foo 42 bar \"s\" nil");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Ok(Node::Unit(Span::new(p(0), p(19)), vec![
+            Node::Keyword(Span::new(p(0), p(4)), None, Box::new("foo".to_string())),
+            Node::Long(Span::new(p(5), p(7)), 42),
+            Node::Symbol(Span::new(p(8), p(11)), None, Box::new("bar".to_string()), None),
+            Node::String(Span::new(p(12), p(15)), Box::new("s".to_string())),
+            Node::Nil(Span::new(p(16), p(19)))
+        ])))
+    }
+
+    #[test]
+    fn typed_literals() {
+        let lexer = LexicalAnalyzer::new("true 2.5 7N 1/3 1.5M \\a ::k #\"x\" ##Inf");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Ok(Node::Unit(Span::new(p(0), p(38)), vec![
+            Node::Bool(Span::new(p(0), p(4)), true),
+            Node::Double(Span::new(p(5), p(8)), 2.5),
+            Node::BigInt(Span::new(p(9), p(11)), Box::new("7".to_string())),
+            Node::Ratio(Span::new(p(12), p(15)), 1, 3),
+            Node::BigDecimal(Span::new(p(16), p(20)), Box::new("1.5".to_string())),
+            Node::Char(Span::new(p(21), p(23)), 'a'),
+            Node::AutoResolvedKeyword(Span::new(p(24), p(27)), None, Box::new("k".to_string())),
+            Node::Regex(Span::new(p(28), p(32)), Box::new("x".to_string())),
+            Node::Double(Span::new(p(33), p(38)), f64::INFINITY)
+        ])));
+        assert_eq!(Node::literal(Symbols::LiteralChar(p(0), p(2), 'a')).and_then(|x| x.token()), Some(Symbols::LiteralChar(p(0), p(2), 'a')));
+        assert_eq!(Node::literal(Symbols::LeftParen(p(0), p(1))), None)
+    }
+
+    #[test]
+    fn empty_list() {
+        let lexer = LexicalAnalyzer::new("()");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Ok(Node::Unit(Span::new(p(0), p(2)), vec![
+            Node::List(Symbols::LeftParen(p(0), p(1)), vec![], Symbols::RightParen(p(1), p(2)), Some(Box::new(with_position(None, p(0)))))
+        ])))
+    }
+
+    #[test]
+    fn nested_list_and_vector() {
+        let lexer = LexicalAnalyzer::new("(def x [a b])");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Ok(Node::Unit(Span::new(p(0), p(13)), vec![
+            Node::List(Symbols::LeftParen(p(0), p(1)), vec![
+                Node::Symbol(Span::new(p(1), p(4)), None, Box::new("def".to_string()), None),
+                Node::Symbol(Span::new(p(5), p(6)), None, Box::new("x".to_string()), None),
+                Node::Vec(Symbols::LeftBracket(p(7), p(8)), vec![
+                    Node::Symbol(Span::new(p(8), p(9)), None, Box::new("a".to_string()), None),
+                    Node::Symbol(Span::new(p(10), p(11)), None, Box::new("b".to_string()), None)
+                ], Symbols::RightBracket(p(11), p(12)), Some(Box::new(with_position(None, p(7)))))
+            ], Symbols::RightParen(p(12), p(13)), Some(Box::new(with_position(None, p(0)))))
+        ])))
+    }
+
+    #[test]
+    fn map_with_key_values() {
+        let lexer = LexicalAnalyzer::new("{:a b :c [d]}");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Ok(Node::Unit(Span::new(p(0), p(13)), vec![
+            Node::Map(Symbols::LeftCurly(p(0), p(1)), vec![
+                Node::KeyValue(
+                    Box::new(Node::Keyword(Span::new(p(1), p(3)), None, Box::new("a".to_string()))),
+                    Box::new(Node::Symbol(Span::new(p(4), p(5)), None, Box::new("b".to_string()), None))
+                ),
+                Node::KeyValue(
+                    Box::new(Node::Keyword(Span::new(p(6), p(8)), None, Box::new("c".to_string()))),
+                    Box::new(Node::Vec(Symbols::LeftBracket(p(9), p(10)), vec![
+                        Node::Symbol(Span::new(p(10), p(11)), None, Box::new("d".to_string()), None)
+                    ], Symbols::RightBracket(p(11), p(12)), Some(Box::new(with_position(None, p(9))))))
+                )
+            ], Symbols::RightCurly(p(12), p(13)), Some(Box::new(with_position(None, p(0)))))
+        ])))
+    }
+
+    #[test]
+    fn multiple_top_level_forms() {
+        let lexer = LexicalAnalyzer::new("(a) [b] {}");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, lst)) => assert_eq!(lst.len(), 3),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn set_literal() {
+        let lexer = LexicalAnalyzer::new("#{:a b}");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Ok(Node::Unit(Span::new(p(0), p(7)), vec![
+            Node::Set(Symbols::LeftCurly(p(0), p(2)), vec![
+                Node::Keyword(Span::new(p(2), p(4)), None, Box::new("a".to_string())),
+                Node::Symbol(Span::new(p(5), p(6)), None, Box::new("b".to_string()), None)
+            ], Symbols::RightCurly(p(6), p(7)), Some(Box::new(with_position(None, p(0)))))
+        ])))
+    }
+
+    #[test]
+    fn nested_set_literal() {
+        let lexer = LexicalAnalyzer::new("[#{} #{[a] #{b}}]");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), "[#{} #{[a] #{b}}]"),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn set_with_duplicate_keys() {
+        let lexer = LexicalAnalyzer::new("#{:a [b c] :d [b  c]}");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::DuplicateKey(Span::new(p(14), p(20)), Box::new("[b c]".to_string()))))
+    }
+
+    #[test]
+    fn set_with_duplicate_numbers() {
+        let lexer = LexicalAnalyzer::new("(+ 1 #{1 2 3 2})");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::DuplicateKey(Span::new(p(13), p(14)), Box::new("2".to_string()))))
+    }
+
+    #[test]
+    fn duplicates_compared_by_value() {
+        let duplicate = |source: &str| match ExpressionParser::new(LexicalAnalyzer::new(source)).parse_start_unit() {
+            Err(SyntaxError::DuplicateKey(span, key)) => Some((span, key.to_string())),
+            Ok(_) => None,
+            x => panic!("Unexpected result {:?}", x)
+        };
+
+        assert_eq!(duplicate("#{1 1N}"), Some((Span::new(p(4), p(6)), "1N".to_string())));
+        assert_eq!(duplicate("#{\\a \\u0061}"), Some((Span::new(p(5), p(11)), "\\a".to_string())));
+        assert_eq!(duplicate("#{^:a x ^:b x}"), Some((Span::new(p(12), p(13)), "x".to_string())));
+        assert_eq!(duplicate("#{[1 2] (1 2)}"), Some((Span::new(p(8), p(13)), "(1 2)".to_string())));
+        assert_eq!(duplicate("#{0.0 -0.0 1.5M 1.50M}").map(|x| x.1), Some("-0.0".to_string()));
+        assert_eq!(duplicate("#{##NaN ##NaN}"), None);
+        assert_eq!(duplicate("#{1 1.0 \"a\" a :a}"), None)
+    }
+
+    #[test]
+    fn map_with_duplicate_keys() {
+        let lexer = LexicalAnalyzer::new("{:a 1 #{2 1} 2 :a 3}");
+        let mut parser = ExpressionParser::new(lexer);
+        assert_eq!(parser.parse_start_unit(), Err(SyntaxError::DuplicateKey(Span::new(p(15), p(17)), Box::new(":a".to_string()))));
+
+        let lexer = LexicalAnalyzer::new("{#{1 2} a #{2 1} b}");
+        let mut parser = ExpressionParser::new(lexer);
+        assert_eq!(parser.parse_start_unit(), Err(SyntaxError::DuplicateKey(Span::new(p(10), p(16)), Box::new("#{2 1}".to_string()))));
+
+        let lexer = LexicalAnalyzer::new("{##NaN 1 ##NaN 2 :a 1 :b 1}");
+        let mut parser = ExpressionParser::new(lexer);
+        assert!(parser.parse_start_unit().is_ok())
+    }
+
+    #[test]
+    fn quote_and_unquote() {
+        let lexer = LexicalAnalyzer::new("'(a b) [~a ~@b]");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => {
+                assert_eq!(nodes[0].to_string(), "(quote (a b))");
+                assert_eq!(nodes[0].span(), Span::new(p(0), p(6)));
+                assert!(matches!(&nodes[0], Node::List(Symbols::LeftParen(x, y), _, Symbols::RightParen(_, z), _) if *x == p(0) && *y == p(0) && *z == p(6)));
+                assert_eq!(nodes[1].to_string(), "[(clojure.core/unquote a) (clojure.core/unquote-splicing b)]")
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn quote_at_end_of_file() {
+        let lexer = LexicalAnalyzer::new("(a ')");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::UnexpectedClosingDelimiter(Span::new(p(4), p(5)))));
+        assert_eq!(ExpressionParser::new(LexicalAnalyzer::new("'")).parse_start_unit(), Err(SyntaxError::UnexpectedEndOfFile(Span::new(p(1), p(1)))))
+    }
+
+    #[test]
+    fn syntax_quote_list() {
+        let lexer = LexicalAnalyzer::new("`(a b/c ~d ~@e if :k 1 java.util.Date)");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), concat!(
+                "(clojure.core/seq (clojure.core/concat (clojure.core/list (quote user/a)) (clojure.core/list (quote b/c)) ",
+                "(clojure.core/list d) e (clojure.core/list (quote if)) (clojure.core/list :k) (clojure.core/list 1) ",
+                "(clojure.core/list (quote java.util.Date))))")),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn syntax_quote_literals() {
+        let lexer = LexicalAnalyzer::new("`(nil true false ##Inf)");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), concat!(
+                "(clojure.core/seq (clojure.core/concat (clojure.core/list nil) (clojure.core/list true) ",
+                "(clojure.core/list false) (clojure.core/list ##Inf)))")),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn syntax_quote_collections() {
+        let lexer = LexicalAnalyzer::new("`[x {:a ~y} ()]");
+        let mut parser = ExpressionParser::new(lexer);
+        parser.set_namespace("my.ns");
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), concat!(
+                "(clojure.core/apply clojure.core/vector (clojure.core/seq (clojure.core/concat (clojure.core/list (quote my.ns/x)) ",
+                "(clojure.core/list (clojure.core/apply clojure.core/hash-map (clojure.core/seq (clojure.core/concat (clojure.core/list :a) (clojure.core/list y))))) ",
+                "(clojure.core/list (clojure.core/list)))))")),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn syntax_quote_auto_gensym() {
+        let lexer = LexicalAnalyzer::new("`(let [x# 1] x#) `x#");
+        let mut parser = ExpressionParser::new(lexer);
+        let text = parser.parse_start_unit().unwrap().to_string();
+
+        let names : Vec<&str> = text.split(['(', ')', ' ', '\n']).filter(|x| x.starts_with("x__")).collect();
+        assert_eq!(names, vec!["x__1__auto__", "x__1__auto__", "x__2__auto__"])
+    }
+
+    #[test]
+    fn syntax_quote_splicing_outside_list() {
+        let lexer = LexicalAnalyzer::new("`~@a");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::UnquoteSplicingNotInList(Span::new(p(1), p(4)))))
+    }
+
+    #[test]
+    fn anonymous_fn() {
+        let lexer = LexicalAnalyzer::new("#(+ % %3 (* %1 %&)) #() #(vector {:a %})");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => {
+                assert_eq!(nodes[0].to_string(), "(fn* [p1__1# p2__2# p3__3# & rest__4#] (+ p1__1# p3__3# (* p1__1# rest__4#)))");
+                assert_eq!(nodes[0].span(), Span::new(p(0), p(19)));
+                assert_eq!(nodes[1].to_string(), "(fn* [] ())");
+                assert_eq!(nodes[2].to_string(), "(fn* [p1__5#] (vector {:a p1__5#}))")
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn anonymous_fn_errors() {
+        let lexer = LexicalAnalyzer::new("#(a #(b))");
+        let mut parser = ExpressionParser::new(lexer);
+        assert_eq!(parser.parse_start_unit(), Err(SyntaxError::NestedAnonymousFn(Span::new(p(4), p(6)))));
+
+        let lexer = LexicalAnalyzer::new("#(a %x)");
+        let mut parser = ExpressionParser::new(lexer);
+        assert_eq!(parser.parse_start_unit(), Err(SyntaxError::InvalidArgLiteral(Span::new(p(4), p(6)), Box::new("%x".to_string()))))
+    }
+
+    #[test]
+    fn var_quote_and_regex() {
+        let lexer = LexicalAnalyzer::new(r#"[#'clojure.core/map #"\d+"]"#);
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => {
+                assert_eq!(nodes[0].to_string(), r#"[(var clojure.core/map) #"\d+"]"#);
+                match &nodes[0] {
+                    Node::Vec(_, items, _, _) => {
+                        assert_eq!(items[0].span(), Span::new(p(1), p(19)));
+                        assert!(matches!(&items[0], Node::List(Symbols::LeftParen(_, _), _, Symbols::RightParen(_, _), _)))
                     },
-                    _ => assert!(false)
+                    x => panic!("Unexpected node {:?}", x)
                 }
             },
-            _ => assert!(false)
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn discard_forms() {
+        let lexer = LexicalAnalyzer::new("#_(ignored) [a #_b c #_ #_ d e f] (g #_h) {:a 1 #_:b}");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), "[a c f]\n(g)\n{:a 1}"),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn reader_conditionals() {
+        let lexer = LexicalAnalyzer::new("#?(:clj 1 :native 2) [a #?(:cljs b) c] [#?@(:clj [x] :default [y z]) w] {#?@(:native (:k 1))} '#?(:default d)");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), "2\n[a c]\n[y z w]\n{:k 1}\n(quote d)"),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn reader_conditional_features() {
+        let lexer = LexicalAnalyzer::new("#?(:native 1 :cljs 2) #?(:bb 3)");
+        let mut parser = ExpressionParser::new(lexer);
+        parser.set_features(&["cljs", "bb"]);
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), "2\n3"),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn reader_conditional_preserved() {
+        let lexer = LexicalAnalyzer::new("#?(:clj 1 :cljs 2) [#?@(:clj [a])]");
+        let mut parser = ExpressionParser::new(lexer);
+        parser.set_preserve_conditionals(true);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => {
+                assert_eq!(nodes[0].to_string(), "#?(:clj 1 :cljs 2)");
+                assert_eq!(nodes[0].span(), Span::new(p(0), p(18)));
+                assert_eq!(nodes[1].to_string(), "[#?@(:clj [a])]")
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn reader_conditional_errors() {
+        let cases = [
+            ("#?[:clj 1]", SyntaxError::ReaderConditionalNotList(Span::new(p(2), p(10)))),
+            ("#?(:clj)", SyntaxError::ReaderConditionalOddForms(Span::new(p(2), p(8)))),
+            ("#?(clj 1)", SyntaxError::FeatureNotKeyword(Span::new(p(3), p(6)))),
+            ("#?@(:native [1])", SyntaxError::SplicingNotInCollection(Span::new(p(0), p(3)))),
+            ("[#?@(:native 1)]", SyntaxError::SplicedFormNotSequential(Span::new(p(13), p(14))))
+        ];
+
+        for (text, error) in cases {
+            let mut parser = ExpressionParser::new(LexicalAnalyzer::new(text));
+            assert_eq!(parser.parse_start_unit(), Err(error))
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn tagged_literals() {
+        let lexer = LexicalAnalyzer::new("[#inst \"2024-01-01\" #inst \"2024-02-29T10:30:00.250-05:00\" #uuid \"5f1c3e4a-9b2d-4c6e-8f0a-1b2c3d4e5f60\"]");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => match &nodes[0] {
+                Node::Vec(_, items, _, _) => {
+                    assert_eq!(nodes[0].to_string(), "[#inst \"2024-01-01\" #inst \"2024-02-29T10:30:00.250-05:00\" #uuid \"5f1c3e4a-9b2d-4c6e-8f0a-1b2c3d4e5f60\"]");
+                    assert_eq!(items[0].span(), Span::new(p(1), p(19)))
+                },
+                x => panic!("Unexpected node {:?}", x)
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn tagged_literal_errors() {
+        let cases = [
+            ("#inst \"2023-02-29\"", SyntaxError::InvalidTaggedLiteral(Span::new(p(0), p(18)), Box::new("Unrecognized date/time syntax: 2023-02-29".to_string()))),
+            ("#inst 2024", SyntaxError::InvalidTaggedLiteral(Span::new(p(0), p(10)), Box::new("Instance literal expects a string for its timestamp.".to_string()))),
+            ("#uuid \"5f1c3e4a\"", SyntaxError::InvalidTaggedLiteral(Span::new(p(0), p(16)), Box::new("Invalid UUID string: 5f1c3e4a".to_string()))),
+            ("#js {}", SyntaxError::UnknownTag(Span::new(p(0), p(6)), Box::new("js".to_string())))
+        ];
+
+        for (text, error) in cases {
+            let mut parser = ExpressionParser::new(LexicalAnalyzer::new(text));
+            assert_eq!(parser.parse_start_unit(), Err(error))
+        }
+    }
+
+    #[test]
+    fn registered_tag_readers() {
+        let lexer = LexicalAnalyzer::new("#?(:cljs #js {} :default #point [1 2]) #my/tag x #inst \"not checked\"");
+        let mut parser = ExpressionParser::new(lexer);
+        parser.register_tag_reader("point", |node| match node {
+            Node::Tagged(_, form) if matches!(form.as_ref(), Node::Vec(_, items, _, _) if items.len() == 2) => Ok(*form),
+            _ => Err("A point is a vector of two numbers".to_string())
+        });
+        parser.register_tag_reader("inst", Ok);
+        assert_eq!(parser.load_data_readers("{my/tag my.readers/read-tag #?(:native other/tag) other.readers/read}"), Ok(()));
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), "[1 2]\n(my.readers/read-tag (quote x))\n#inst \"not checked\""),
+            x => panic!("Unexpected result {:?}", x)
+        }
+        assert_eq!(parser.load_data_readers("{tag read}"), Err(SyntaxError::InvalidDataReaders(Span::new(p(1), p(4)))))
+    }
+
+    #[test]
+    fn error_recovery() {
+        let lexer = LexicalAnalyzer::new("(a ] b) {:k} [c \\xyz #_] (d #?(:x)) ) (e");
+        let mut parser = ExpressionParser::new(lexer);
+        let (unit, diagnostics) = parser.parse_start_unit_recovering();
+
+        assert_eq!(unit.to_string(), "(a <error> b)\n<error>\n[c <error> <error>]\n(d <error>)\n<error>\n(e)");
+        assert_eq!(diagnostics, vec![
+            SyntaxError::MismatchedDelimiter(Span::new(p(3), p(4)), ']', ')', Span::new(p(0), p(1))),
+            SyntaxError::OddNumberOfMapForms(Span::new(p(8), p(12))),
+            SyntaxError::UnsupportedCharacter(Span::new(p(16), p(20)), Box::new("xyz".to_string())),
+            SyntaxError::UnexpectedClosingDelimiter(Span::new(p(23), p(24))),
+            SyntaxError::ReaderConditionalOddForms(Span::new(p(30), p(34))),
+            SyntaxError::UnmatchedDelimiter(Span::new(p(36), p(37)), ')'),
+            SyntaxError::MissingClosingDelimiter(Span::new(p(40), p(40)), ')', Span::new(p(38), p(39)), None)
+        ]);
+        assert_eq!(parser.parse_start_unit_recovering().1, vec![])
+    }
+
+    #[test]
+    fn error_recovery_without_errors() {
+        let mut parser = ExpressionParser::new(LexicalAnalyzer::new("(a) [b]"));
+        let (unit, diagnostics) = parser.parse_start_unit_recovering();
+
+        assert_eq!(unit.to_string(), "(a)\n[b]");
+        assert_eq!(diagnostics, vec![])
+    }
+
+    #[test]
+    fn metadata_merged() {
+        let lexer = LexicalAnalyzer::new("^:private ^String ^{:doc \"d\" :private false} x");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => {
+                assert_eq!(nodes[0].to_string(), "^{:doc \"d\", :private true, :tag String} x");
+                assert_eq!(nodes[0].span(), Span::new(p(45), p(46)));
+                assert_eq!(nodes[0].meta_value("private"), Some(&Node::Bool(Span::new(p(9), p(9)), true)));
+                assert_eq!(nodes[0].meta_value("doc").map(|x| x.to_string()), Some("\"d\"".to_string()));
+                assert_eq!(nodes[0].meta_value("line"), Some(&Node::Long(Span::new(p(45), p(45)), 1)));
+                assert_eq!(nodes[0].meta_value("column"), Some(&Node::Long(Span::new(p(45), p(45)), 46)));
+                assert_eq!(nodes[0].without_meta(), Node::Symbol(Span::new(p(45), p(46)), None, Box::new("x".to_string()), None))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn metadata_auto_resolved_keyword() {
+        let lexer = LexicalAnalyzer::new("^::private ^::str/pure x");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => {
+                assert_eq!(nodes[0].to_string(), "^{::str/pure true, ::private true} x");
+                assert_eq!(nodes[0].meta_value("private"), None);
+                assert_eq!(nodes[0].without_meta(), Node::Symbol(Span::new(p(23), p(24)), None, Box::new("x".to_string()), None))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn metadata_has_reader_positions() {
+        let lexer = LexicalAnalyzer::new("[a]
+  (f [b] ^{:line 9} (g))
+ #{c} {:d 'e}");
+        let mut parser = ExpressionParser::new(lexer);
+        let position = |x: &Node| (x.meta_value("line").map(|x| x.to_string()), x.meta_value("column").map(|x| x.to_string()));
+        let at = |line: &str, column: &str| (Some(line.to_string()), Some(column.to_string()));
+
+        match parser.parse_start_unit() {
+            Ok(Node::Unit(_, nodes)) => {
+                assert_eq!(position(&nodes[0]), at("1", "1"));
+                assert_eq!(position(nodes[0].children()[0]), (None, None));
+                assert_eq!(nodes[1].meta_value("line").map(|x| x.to_string()), Some("2".to_string()));
+                assert_eq!(nodes[1].meta_value("column").map(|x| x.to_string()), Some("3".to_string()));
+                assert_eq!(position(nodes[1].children()[1]), at("2", "6"));
+                assert_eq!(position(&nodes[2]), at("3", "2"));
+                assert_eq!(position(&nodes[3]), at("3", "7"));
+                assert_eq!(position(nodes[3].children()[0].children()[1]), at("3", "11"));
+                assert_eq!(nodes[3].to_string(), "{:d (quote e)}");
+                assert_eq!(nodes[1].children()[2].meta_value("line").map(|x| x.to_string()), Some("9".to_string()));
+                assert_eq!(nodes[1].children()[2].meta_value("column").map(|x| x.to_string()), Some("21".to_string()));
+                assert_eq!(nodes[1].to_string(), "(f [b] (g))")
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn metadata_on_collections() {
+        let lexer = LexicalAnalyzer::new("(defn ^:no-doc f ^[long] [a] ^:once (g)) `^String x");
+        let mut parser = ExpressionParser::new(lexer);
+
+        match parser.parse_start_unit() {
+            Ok(x) => assert_eq!(x.to_string(), concat!(
+                "(defn ^{:no-doc true} f ^{:param-tags [long]} [a] ^{:once true} (g))\n",
+                "(clojure.core/with-meta (quote user/x) (clojure.core/apply clojure.core/hash-map ",
+                "(clojure.core/seq (clojure.core/concat (clojure.core/list :tag) (clojure.core/list (quote user/String))))))")),
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn metadata_errors() {
+        let lexer = LexicalAnalyzer::new("[^:a 1]");
+        let mut parser = ExpressionParser::new(lexer);
+        assert_eq!(parser.parse_start_unit(), Err(SyntaxError::InvalidMetadataTarget(Span::new(p(5), p(6)))));
+
+        let lexer = LexicalAnalyzer::new("[^1 x]");
+        let mut parser = ExpressionParser::new(lexer);
+        assert_eq!(parser.parse_start_unit(), Err(SyntaxError::InvalidMetadata(Span::new(p(2), p(3)))))
+    }
+
+    #[test]
+    fn hash_separated_from_curly() {
+        let lexer = LexicalAnalyzer::new("# {}");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::MissingDispatchForm(Span::new(p(0), p(1)))))
+    }
+
+    #[test]
+    fn node_spans() {
+        let lexer = LexicalAnalyzer::new("(a\n {:k\n  v})");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit().unwrap();
+
+        assert_eq!(res.span(), Span::new(p(0), Position::new(0, 3, 6, 13)));
+        match &res {
+            Node::Unit(_, nodes) => match &nodes[0] {
+                Node::List(_, items, _, _) => {
+                    assert_eq!(nodes[0].span(), Span::new(p(0), Position::new(0, 3, 6, 13)));
+                    assert_eq!(items[1].span(), Span::new(Position::new(0, 2, 2, 4), Position::new(0, 3, 5, 12)));
+                    match &items[1] {
+                        Node::Map(_, entries, _, _) => assert_eq!(entries[0].span(), Span::new(Position::new(0, 2, 3, 5), Position::new(0, 3, 4, 11))),
+                        x => panic!("Unexpected node {:?}", x)
+                    }
+                },
+                x => panic!("Unexpected node {:?}", x)
+            },
+            x => panic!("Unexpected node {:?}", x)
+        }
+    }
+
+    #[test]
+    fn map_with_odd_number_of_forms() {
+        let lexer = LexicalAnalyzer::new("{:a b :c}");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::OddNumberOfMapForms(Span::new(p(0), p(9)))))
+    }
+
+    #[test]
+    fn unterminated_list() {
+        let lexer = LexicalAnalyzer::new("(a (b)");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::MissingClosingDelimiter(Span::new(p(6), p(6)), ')', Span::new(p(0), p(1)), None)))
+    }
+
+    #[test]
+    fn mismatched_delimiter() {
+        let lexer = LexicalAnalyzer::new("(a]");
+        let mut parser = ExpressionParser::new(lexer);
+        let res = parser.parse_start_unit();
+
+        assert_eq!(res, Err(SyntaxError::MismatchedDelimiter(Span::new(p(2), p(3)), ']', ')', Span::new(p(0), p(1)))))
+    }
+}
@@ -0,0 +1,362 @@
+use super::expression_parser::{ ExpressionParser, ExpressionParserMethods, Node };
+use super::lexical_analyzer::{ LexicalAnalyzer, LexicalAnalyzerMethods, Symbols };
+use super::source_position::Position;
+use super::syntax_error::SyntaxError;
+use std::io::BufRead;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum ReadResult {
+    Form(Node),
+    Incomplete,
+    EndOfInput
+}
+
+pub trait IncrementalReaderMethods {
+    fn new() -> Self;
+    fn new_with_file_id(file_id: u32) -> Self;
+    fn with_parser(parser: ExpressionParser<'static>, file_id: u32) -> Self;
+    fn push_str(&mut self, text: &str);
+    fn fill_from(&mut self, input: &mut impl BufRead) -> std::io::Result<bool>;
+    fn finish(&mut self);
+    fn pending(&self) -> &str;
+    fn read_form(&mut self) -> Result<ReadResult, SyntaxError>;
+}
+
+/* Reads one top level form at a time from input that arrives in pieces, like a REPL or a pipe.
+ * Text is buffered until it holds a complete form, read forms are dropped from the buffer
+ * while positions keep counting from the start of the stream.
+ * New text is scanned from where the last scan stopped, and a form is only parsed once its delimiters are balanced.
+ */
+pub struct IncrementalReader {
+    buffer: String,
+    start: Position,
+    scanned: Position,
+    depth: usize,
+    finished: bool,
+    parser: ExpressionParser<'static>
+}
+
+impl IncrementalReaderMethods for IncrementalReader {
+
+    fn new() -> Self {
+        IncrementalReader::new_with_file_id(0)
+    }
+
+    fn new_with_file_id(file_id: u32) -> Self {
+        IncrementalReader::with_parser(ExpressionParser::new(LexicalAnalyzer::new("")), file_id)
+    }
+
+    /* Forms are read with the namespace, features and tag readers of the parser, its own input is not used */
+    fn with_parser(parser: ExpressionParser<'static>, file_id: u32) -> Self {
+        IncrementalReader {
+            buffer: String::new(),
+            start: Position::start_of_file(file_id),
+            scanned: Position::start_of_file(file_id),
+            depth: 0,
+            finished: false,
+            parser
+        }
+    }
+
+    fn push_str(&mut self, text: &str) {
+        self.buffer.push_str(text)
+    }
+
+    /* Buffer the next line of input, returns false and finishes the reader at end of input */
+    fn fill_from(&mut self, input: &mut impl BufRead) -> std::io::Result<bool> {
+        match input.read_line(&mut self.buffer)? {
+            0 => {
+                self.finish();
+                Ok(false)
+            },
+            _ => Ok(true)
+        }
+    }
+
+    /* No more input will arrive, unfinished forms are now errors */
+    fn finish(&mut self) {
+        self.finished = true
+    }
+
+    fn pending(&self) -> &str {
+        &self.buffer
+    }
+
+    /* Incomplete means more input is needed, after a syntax error the input up to the end of the broken form is dropped.
+     * The buffer up to where a form may end is parsed, a form that turns out to need more is parsed again at the next such point.
+     */
+    fn read_form(&mut self) -> Result<ReadResult, SyntaxError> {
+        loop {
+            let end = match self.scan() {
+                Some(end) => end,
+                None => return Ok(ReadResult::Incomplete)
+            };
+            let length = (end.offset - self.start.offset) as usize;
+            self.parser.set_lexer(LexicalAnalyzer::new_at_position(self.buffer[..length].to_string(), self.start));
+
+            match self.parser.parse_start_form() {
+                Ok(Some(node)) => {
+                    self.consume_to(end);
+                    return Ok(ReadResult::Form(node))
+                },
+                Ok(None) if length == self.buffer.len() && self.finished => {
+                    self.consume_to(end);
+                    return Ok(ReadResult::EndOfInput)
+                },
+                Ok(None) => self.consume_to(end),
+                Err(error) if is_incomplete(&error) && (length < self.buffer.len() || !self.finished) => (),
+                /* Only the broken form is dropped, the forms after it are still read */
+                Err(error) => {
+                    self.consume_to(end);
+                    return Err(error)
+                }
+            }
+        }
+    }
+}
+
+impl IncrementalReader {
+    /* Lexes the text after the last scan, counting open delimiters, up to the end of a token that may close a top level form.
+     * A token touching the end of the buffer may continue in the next piece of input, so it is left for the next scan.
+     * None when more input is needed, at the end of a finished input the end of the buffer is given.
+     */
+    fn scan(&mut self) -> Option<Position> {
+        let from = (self.scanned.offset - self.start.offset) as usize;
+        let end = self.start.offset + self.buffer.len() as u32;
+        let mut lexer = LexicalAnalyzer::new_at_position(&self.buffer[from..], self.scanned);
+
+        loop {
+            let before = lexer.position();
+            let symbol = lexer.get_symbol();
+            let at_end = lexer.position().offset == end && !self.finished;
+            match symbol {
+                Ok(Symbols::EOF(_, _)) if self.finished => {
+                    self.scanned = lexer.position();
+                    return Some(self.scanned)
+                },
+                /* A comment may go on in the next piece, whitespace up to the last line break is done */
+                Ok(Symbols::EOF(_, _)) => {
+                    let skipped = &self.buffer[(before.offset - self.start.offset) as usize..];
+                    let mut lexer = LexicalAnalyzer::new_at_position(skipped, before);
+                    if let Some(index) = skipped.rfind('\n') {
+                        while lexer.position().offset <= before.offset + index as u32 {
+                            lexer.advance()
+                        }
+                    }
+                    self.scanned = lexer.position();
+                    return None
+                },
+                Ok(Symbols::RightParen(_, _) | Symbols::RightBracket(_, _) | Symbols::RightCurly(_, _)) => self.depth = self.depth.saturating_sub(1),
+                /* A closing delimiter that does not fit ends the form, reading it gives the error */
+                Err(SyntaxError::UnmatchedDelimiter(_, _) | SyntaxError::MismatchedDelimiter(_, _, _, _)) => {
+                    self.depth = 0;
+                    self.scanned = lexer.position();
+                    return Some(self.scanned)
+                },
+                Ok(Symbols::LeftParen(_, _) | Symbols::LeftBracket(_, _) | Symbols::LeftCurly(_, _)) => self.depth += 1,
+                Ok(Symbols::LiteralString(_, _, _) | Symbols::LiteralRegex(_, _, _)) => (),
+                Err(error) if is_incomplete(&error) && !self.finished => {
+                    self.scanned = before;
+                    return None
+                },
+                _ if at_end => {
+                    self.scanned = before;
+                    return None
+                },
+                /* Prefixes like ' and ^ need the form after them */
+                Ok(Symbols::Hash(_, _) | Symbols::Quote(_, _) | Symbols::SyntaxQuote(_, _) | Symbols::Unquote(_, _) |
+                   Symbols::UnquoteSplicing(_, _) | Symbols::Discard(_, _) | Symbols::Caret(_, _) |
+                   Symbols::ReaderConditional(_, _) | Symbols::ReaderConditionalSplicing(_, _)) => {
+                    self.scanned = lexer.position();
+                    continue
+                },
+                _ => ()
+            }
+            self.scanned = lexer.position();
+            if self.depth == 0 {
+                return Some(self.scanned)
+            }
+        }
+    }
+
+    /* Drop the text before position from the buffer */
+    fn consume_to(&mut self, position: Position) {
+        self.buffer.drain(..(position.offset - self.start.offset) as usize);
+        self.start = position;
+        if self.scanned.offset < position.offset {
+            self.scanned = position
+        }
+    }
+}
+
+impl Default for IncrementalReader {
+    fn default() -> Self {
+        IncrementalReader::new()
+    }
+}
+
+/* Errors caused only by the input ending too early */
+fn is_incomplete(error: &SyntaxError) -> bool {
+    matches!(error,
+        SyntaxError::UnexpectedEndOfFile(_) |
+        SyntaxError::MissingClosingDelimiter(_, _, _, _) |
+        SyntaxError::UnterminatedString(_) |
+        SyntaxError::UnterminatedRegex(_) |
+        SyntaxError::UnterminatedCharacter(_))
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::expression_parser::{ ExpressionParser, ExpressionParserMethods, Node };
+    use crate::parser::lexical_analyzer::{ LexicalAnalyzer, LexicalAnalyzerMethods };
+    use crate::parser::source_position::{Position, Span};
+    use crate::parser::syntax_error::SyntaxError;
+
+    use super::{IncrementalReader, IncrementalReaderMethods, ReadResult};
+
+    fn read_text(reader: &mut IncrementalReader) -> Option<String> {
+        match reader.read_form() {
+            Ok(ReadResult::Form(x)) => Some(x.to_string()),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn one_form_at_a_time() {
+        let mut reader = IncrementalReader::new();
+        reader.push_str("(def x 1) [x]\n");
+
+        assert_eq!(read_text(&mut reader), Some("(def x 1)".to_string()));
+        assert_eq!(read_text(&mut reader), Some("[x]".to_string()));
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete));
+        assert_eq!(reader.pending(), "\n")
+    }
+
+    #[test]
+    fn incomplete_until_more_input() {
+        let mut reader = IncrementalReader::new();
+        reader.push_str("(+ 1\n");
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete));
+        reader.push_str("   \"a");
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete));
+        reader.push_str("b\" 2");
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete));
+        reader.push_str(")");
+
+        match reader.read_form() {
+            Ok(ReadResult::Form(x)) => {
+                assert_eq!(x.to_string(), "(+ 1 \"ab\" 2)");
+                assert_eq!(x.span(), Span::new(Position::new(0, 1, 1, 0), Position::new(0, 2, 11, 15)))
+            },
+            x => panic!("Unexpected result {:?}", x)
+        }
+    }
+
+    #[test]
+    fn symbol_at_end_of_input() {
+        let mut reader = IncrementalReader::new();
+        reader.push_str("[a] fo");
+        assert_eq!(read_text(&mut reader), Some("[a]".to_string()));
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete));
+        reader.push_str("o");
+        reader.finish();
+
+        assert_eq!(read_text(&mut reader), Some("foo".to_string()));
+        assert_eq!(reader.read_form(), Ok(ReadResult::EndOfInput))
+    }
+
+    #[test]
+    fn error_drops_broken_form() {
+        let mut reader = IncrementalReader::new();
+        reader.push_str("(a]\n(b)");
+
+        assert_eq!(reader.read_form(), Err(SyntaxError::MismatchedDelimiter(Span::new(Position::new(0, 1, 3, 2), Position::new(0, 1, 4, 3)), ']', ')',
+            Span::new(Position::new(0, 1, 1, 0), Position::new(0, 1, 2, 1)))));
+        assert_eq!(reader.pending(), "\n(b)");
+        assert_eq!(read_text(&mut reader), Some("(b)".to_string()));
+        reader.push_str(" ((c] d) \\xyz (e)\n");
+        assert!(matches!(reader.read_form(), Err(SyntaxError::MismatchedDelimiter(_, ']', ')', _))));
+        assert_eq!(read_text(&mut reader), Some("d".to_string()));
+        assert!(matches!(reader.read_form(), Err(SyntaxError::UnmatchedDelimiter(_, ')'))));
+        assert!(reader.read_form().is_err());
+        assert_eq!(read_text(&mut reader), Some("(e)".to_string()))
+    }
+
+    #[test]
+    fn finished_with_open_form() {
+        let mut reader = IncrementalReader::new();
+        reader.push_str("(a (b)");
+        reader.finish();
+
+        assert_eq!(reader.read_form(), Err(SyntaxError::MissingClosingDelimiter(Span::new(Position::new(0, 1, 7, 6), Position::new(0, 1, 7, 6)), ')',
+            Span::new(Position::new(0, 1, 1, 0), Position::new(0, 1, 2, 1)), None)))
+    }
+
+    #[test]
+    fn configured_parser() {
+        let mut parser = ExpressionParser::new(LexicalAnalyzer::new(""));
+        parser.set_namespace("my.ns");
+        parser.set_features(&["cljs"]);
+        parser.register_tag_reader("point", |node| match node {
+            Node::Tagged(_, form) => Ok(*form),
+            _ => Err("Expecting a tagged form".to_string())
+        });
+        let mut reader = IncrementalReader::with_parser(parser, 0);
+        reader.push_str("`x #?(:clj 1 :cljs 2) #point [1 2] #(inc %)\n#(inc %)\n");
+
+        assert_eq!(read_text(&mut reader), Some("(quote my.ns/x)".to_string()));
+        assert_eq!(read_text(&mut reader), Some("2".to_string()));
+        assert_eq!(read_text(&mut reader), Some("[1 2]".to_string()));
+        assert_eq!(read_text(&mut reader), Some("(fn* [p1__1#] (inc p1__1#))".to_string()));
+        assert_eq!(read_text(&mut reader), Some("(fn* [p1__2#] (inc p1__2#))".to_string()))
+    }
+
+    #[test]
+    fn split_across_pieces() {
+        let mut reader = IncrementalReader::new();
+        reader.push_str("; a");
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete));
+        reader.push_str("b (c)\n#");
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete));
+        reader.push_str("{1}");
+
+        assert_eq!(read_text(&mut reader), Some("#{1}".to_string()));
+        assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete))
+    }
+
+    #[test]
+    fn one_char_at_a_time() {
+        let source = "(defn f [x] {:a \"b\", :c [x 2.5]})";
+        let mut reader = IncrementalReader::new();
+        for (index, c) in source.char_indices() {
+            assert_eq!(reader.read_form(), Ok(ReadResult::Incomplete), "after {}", &source[..index]);
+            reader.push_str(&c.to_string())
+        }
+
+        assert_eq!(read_text(&mut reader), Some(source.to_string()))
+    }
+
+    #[test]
+    fn lines_from_buffered_input() {
+        let mut input = "(println\n  \"hi\")\n:done\n".as_bytes();
+        let mut reader = IncrementalReader::new();
+        let mut forms = Vec::new();
+
+        loop {
+            match reader.read_form() {
+                Ok(ReadResult::Form(x)) => forms.push(x.to_string()),
+                Ok(ReadResult::Incomplete) => { reader.fill_from(&mut input).unwrap(); },
+                Ok(ReadResult::EndOfInput) => break,
+                Err(x) => panic!("Unexpected error {:?}", x)
+            }
+        }
+
+        assert_eq!(forms, vec!["(println \"hi\")".to_string(), ":done".to_string()])
+    }
+}
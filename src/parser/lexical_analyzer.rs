@@ -1,267 +1,574 @@
+use super::source_position::{ Position, Span };
+use super::syntax_error::SyntaxError;
+use std::borrow::Cow;
+use std::io::Read;
+use std::path::Path;
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum Symbols {
-    EOF,
-    LeftParen(u32, u32),
-    RightParen(u32, u32),
-    LeftBracket(u32, u32),
-    RightBracket(u32, u32),
-    LeftCurly(u32, u32),
-    RightCurly(u32, u32),
-    Hash(u32, u32),
-    Plus(u32, u32),
-    Minus(u32, u32),
-    Mul(u32, u32),
-    Divide(u32, u32),
-    Questionmark(u32, u32),
-
-    Apply(u32, u32),
-    Def(u32, u32),
-    Defn(u32, u32),
-    Defrecord(u32, u32),
-    Doc(u32, u32),
-    Fn(u32, u32),
-    First(u32, u32),
-    Get(u32, u32),
-    If(u32, u32),
-    Let(u32, u32),
-    Map(u32, u32),
-    Name(u32, u32),
-    Require(u32, u32),
-    Second(u32, u32),
-    When(u32, u32),
-    Less(u32, u32),
-    Greater(u32, u32),
-    Equal(u32, u32),
-    NotEqual(u32, u32),
-
-    LiteralName(u32, u32, Box<String>),
-    LiteralKeyword(u32, u32, Box<String>)
+    EOF(Position, Position),
+    LeftParen(Position, Position),
+    RightParen(Position, Position),
+    LeftBracket(Position, Position),
+    RightBracket(Position, Position),
+    LeftCurly(Position, Position),
+    RightCurly(Position, Position),
+    Hash(Position, Position),
+    Quote(Position, Position),
+    SyntaxQuote(Position, Position),
+    Unquote(Position, Position),
+    UnquoteSplicing(Position, Position),
+    Discard(Position, Position),
+    Caret(Position, Position),
+    ReaderConditional(Position, Position),
+    ReaderConditionalSplicing(Position, Position),
+
+    LiteralNil(Position, Position),
+    LiteralBoolean(Position, Position, bool),
+    LiteralName(Position, Position, Option<Box<String>>, Box<String>),
+    LiteralKeyword(Position, Position, Option<Box<String>>, Box<String>),
+    LiteralAutoResolvedKeyword(Position, Position, Option<Box<String>>, Box<String>),
+    LiteralInteger(Position, Position, i64),
+    LiteralBigInteger(Position, Position, Box<String>),
+    LiteralRatio(Position, Position, i64, i64),
+    LiteralBigRatio(Position, Position, Box<String>, Box<String>),
+    LiteralFloat(Position, Position, f64),
+    LiteralBigDecimal(Position, Position, Box<String>),
+    LiteralString(Position, Position, Box<String>),
+    LiteralChar(Position, Position, char),
+    LiteralRegex(Position, Position, Box<String>)
 }
 
-pub trait LexicalAnalyzerMethods {
-    fn new(input: &'static str) -> Self;
+impl Symbols {
+    pub fn span(&self) -> Span {
+        match self {
+            Symbols::EOF(start, end) |
+            Symbols::LeftParen(start, end) |
+            Symbols::RightParen(start, end) |
+            Symbols::LeftBracket(start, end) |
+            Symbols::RightBracket(start, end) |
+            Symbols::LeftCurly(start, end) |
+            Symbols::RightCurly(start, end) |
+            Symbols::Hash(start, end) |
+            Symbols::Quote(start, end) |
+            Symbols::SyntaxQuote(start, end) |
+            Symbols::Unquote(start, end) |
+            Symbols::UnquoteSplicing(start, end) |
+            Symbols::Discard(start, end) |
+            Symbols::Caret(start, end) |
+            Symbols::ReaderConditional(start, end) |
+            Symbols::ReaderConditionalSplicing(start, end) |
+            Symbols::LiteralNil(start, end) |
+            Symbols::LiteralBoolean(start, end, _) |
+            Symbols::LiteralName(start, end, _, _) |
+            Symbols::LiteralKeyword(start, end, _, _) |
+            Symbols::LiteralAutoResolvedKeyword(start, end, _, _) |
+            Symbols::LiteralInteger(start, end, _) |
+            Symbols::LiteralBigInteger(start, end, _) |
+            Symbols::LiteralRatio(start, end, _, _) |
+            Symbols::LiteralBigRatio(start, end, _, _) |
+            Symbols::LiteralFloat(start, end, _) |
+            Symbols::LiteralBigDecimal(start, end, _) |
+            Symbols::LiteralString(start, end, _) |
+            Symbols::LiteralChar(start, end, _) |
+            Symbols::LiteralRegex(start, end, _) => Span::new(*start, *end)
+        }
+    }
+}
+
+pub trait LexicalAnalyzerMethods<'a> {
+    fn new(input: impl Into<Cow<'a, str>>) -> Self;
+    fn new_with_file_id(input: impl Into<Cow<'a, str>>, file_id: u32) -> Self;
+    fn new_at_position(input: impl Into<Cow<'a, str>>, start: Position) -> Self;
+    fn from_reader(reader: impl Read, file_id: u32) -> std::io::Result<Self> where Self: Sized;
+    fn from_file(path: impl AsRef<Path>, file_id: u32) -> std::io::Result<Self> where Self: Sized;
+    fn source(&self) -> &str;
+    fn position(&self) -> Position;
     fn get_char(&self) -> char;
+    fn peek_char(&self) -> char;
     fn advance(&mut self) -> ();
+    fn open_delimiters(&self) -> &[(char, Span)];
+    fn missing_closing_delimiter(&mut self, end: Span) -> SyntaxError;
+    fn closing_suggestion(&self, opened: Span) -> Option<Position>;
 
     fn is_operator_or_delimiter(&mut self) -> Option<Symbols>;
-    fn is_reserved_keywords(&mut self, text: &str, start: u32, end: u32) -> Option<Symbols>; 
-
-    fn get_symbol(&mut self) -> Result<Symbols, Box<String>>;
+    fn is_number_literal(&mut self) -> Result<Option<Symbols>, SyntaxError>;
+    fn is_string_literal(&mut self) -> Result<Option<Symbols>, SyntaxError>;
+    fn is_regex_literal(&mut self) -> Result<Option<Symbols>, SyntaxError>;
+    fn is_char_literal(&mut self) -> Result<Option<Symbols>, SyntaxError>;
+    fn is_symbolic_value(&mut self) -> Result<Option<Symbols>, SyntaxError>;
+    fn is_symbol_or_keyword(&mut self) -> Result<Option<Symbols>, SyntaxError>;
+
+    fn get_symbol(&mut self) -> Result<Symbols, SyntaxError>;
 }
 
-pub struct LexicalAnalyzer {
-    buffer: Vec<char>,
-    index: u32,
-    stack_elements: Vec::<char>,
-    parenthesis_mismatch_message: Box<String>,
-    parenthesis_mismatch: bool
+/* The input is kept as UTF-8 text, borrowed when possible, and the byte offset in position less the start offset is the read index */
+pub struct LexicalAnalyzer<'a> {
+    buffer: Cow<'a, str>,
+    start_offset: u32,
+    position: Position,
+    stack_elements: Vec::<(char, Span)>,
+    parenthesis_mismatch: Option<SyntaxError>
 }
 
-impl LexicalAnalyzerMethods for LexicalAnalyzer {
+impl<'a> LexicalAnalyzerMethods<'a> for LexicalAnalyzer<'a> {
 
-    fn new(input: &'static str) -> Self {
+    fn new(input: impl Into<Cow<'a, str>>) -> Self {
+        LexicalAnalyzer::new_with_file_id(input, 0)
+    }
+
+    fn new_with_file_id(input: impl Into<Cow<'a, str>>, file_id: u32) -> Self {
+        LexicalAnalyzer::new_at_position(input, Position::start_of_file(file_id))
+    }
+
+    /* Lex input that continues a source at the given position, used when reading a stream piece by piece */
+    fn new_at_position(input: impl Into<Cow<'a, str>>, start: Position) -> Self {
         LexicalAnalyzer {
-            buffer: input.chars().collect(),
-            index: 0,
-            stack_elements: Vec::<char>::new(),
-            parenthesis_mismatch_message: Box::new(String::new()),
-            parenthesis_mismatch: false
+            buffer: input.into(),
+            start_offset: start.offset,
+            position: start,
+            stack_elements: Vec::new(),
+            parenthesis_mismatch: None
         }
     }
 
+    /* Invalid UTF-8 is reported as an io::Error of kind InvalidData */
+    fn from_reader(mut reader: impl Read, file_id: u32) -> std::io::Result<Self> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text)?;
+        Ok(LexicalAnalyzer::new_with_file_id(text, file_id))
+    }
+
+    fn from_file(path: impl AsRef<Path>, file_id: u32) -> std::io::Result<Self> {
+        LexicalAnalyzer::from_reader(std::fs::File::open(path)?, file_id)
+    }
+
+    fn source(&self) -> &str {
+        &self.buffer
+    }
+
     fn get_char(&self) -> char {
-        match self.buffer.get(self.index as usize) {
-			Some(x) => {
-				return x.clone()
-			},
-			_ => '\0'
-		}
+        self.buffer[(self.position.offset - self.start_offset) as usize..].chars().next().unwrap_or('\0')
+    }
+
+    fn peek_char(&self) -> char {
+        self.buffer[(self.position.offset - self.start_offset) as usize..].chars().nth(1).unwrap_or('\0')
+    }
+
+    fn position(&self) -> Position {
+        self.position
+    }
+
+    /* Moves to the next character, keeping line, column and byte offset up to date */
+    fn advance(&mut self) {
+        let ch = self.get_char();
+        if ch == '\0' {
+            return
+        }
+
+        self.position.offset += ch.len_utf8() as u32;
+        match ch {
+            '\n' => {
+                self.position.line += 1;
+                self.position.column = 1
+            },
+            _ => self.position.column += 1
+        }
     }
 
-    fn advance(&mut self) -> () {
-        self.index = self.index + 1
+    /* Delimiters opened and not yet closed, innermost last */
+    fn open_delimiters(&self) -> &[(char, Span)] {
+        &self.stack_elements
+    }
+
+    /* At end of file the innermost open delimiter is reported and dropped, so enclosing collections report their own */
+    fn missing_closing_delimiter(&mut self, end: Span) -> SyntaxError {
+        match self.stack_elements.pop() {
+            Some((open, opened)) => SyntaxError::MissingClosingDelimiter(end, closing_of(open), opened, self.closing_suggestion(opened)),
+            None => SyntaxError::UnexpectedEndOfFile(end)
+        }
+    }
+
+    /* Guesses where the delimiter should have been closed from the indentation, at the end of the last line before
+     * the first line that starts at or left of the opening column. None when the end of file is the best guess.
+     */
+    fn closing_suggestion(&self, opened: Span) -> Option<Position> {
+        let index = (opened.start.offset - self.start_offset) as usize;
+        let line_start = self.buffer[..index].rfind('\n').map(|x| x + 1).unwrap_or(0);
+        let mut offset = line_start;
+        let mut last_line_end = None;
+
+        for (line, text) in (opened.start.line..).zip(self.buffer[line_start..].split('\n')) {
+            let content = text.trim_end();
+            let code = content.trim_start();
+            if !code.is_empty() && !code.starts_with(';') {
+                let column = (content.chars().count() - code.chars().count() + 1) as u32;
+                if line > opened.start.line && column <= opened.start.column {
+                    return last_line_end
+                }
+                let end = self.start_offset + (offset + content.len()) as u32;
+                last_line_end = Some(Position::new(opened.start.file_id, line, content.chars().count() as u32 + 1, end))
+            }
+            offset += text.len() + 1
+        }
+        None
     }
 
     fn is_operator_or_delimiter(&mut self,) -> Option<Symbols> {
         let ch1 = self.get_char();
-        let start = self.index;
+        let start = self.position;
         match &ch1 {
-            '(' => {
-                self.stack_elements.push(ch1);
+            '(' | '[' | '{' => {
                 self.advance();
-                Some(Symbols::LeftParen(start, self.index))
+                self.stack_elements.push((ch1, Span::new(start, self.position)));
+                Some(match ch1 {
+                    '(' => Symbols::LeftParen(start, self.position),
+                    '[' => Symbols::LeftBracket(start, self.position),
+                    _ => Symbols::LeftCurly(start, self.position)
+                })
             },
-            '[' => {
-                self.stack_elements.push(ch1);
+            ')' | ']' | '}' => {
                 self.advance();
-                Some(Symbols::LeftBracket(start, self.index))
-            },
-            '{' => {
-                self.stack_elements.push(ch1);
-                self.advance();
-                Some(Symbols::LeftCurly(start, self.index))
-            },
-            ')' => {
+                let span = Span::new(start, self.position);
                 match self.stack_elements.last() {
-                    Some(x) => {
-                        match &x {
-                            '(' => {
-                                self.stack_elements.pop();
-                                self.advance();
-                                Some(Symbols::RightParen(start, self.index))
-                            },
-                            _ => {
-                                self.parenthesis_mismatch = true;
-                                self.parenthesis_mismatch_message = Box::new("Closing ')' without opening '('".to_string());
-                                None
-                            }
-                        }
+                    Some((open, _)) if closing_of(*open) == ch1 => {
+                        self.stack_elements.pop();
+                        Some(match ch1 {
+                            ')' => Symbols::RightParen(start, self.position),
+                            ']' => Symbols::RightBracket(start, self.position),
+                            _ => Symbols::RightCurly(start, self.position)
+                        })
                     },
-                    _ => {
-                        self.parenthesis_mismatch = true;
-                        self.parenthesis_mismatch_message = Box::new("Closing ')' without opening parentesis".to_string());
+                    Some((open, opened)) => {
+                        self.parenthesis_mismatch = Some(SyntaxError::MismatchedDelimiter(span, ch1, closing_of(*open), *opened));
                         None
-                    }
-                }
-            },
-            ']' => {
-                match self.stack_elements.last() {
-                    Some(x) => {
-                        match &x {
-                            '[' => {
-                                self.stack_elements.pop();
-                                self.advance();
-                                Some(Symbols::RightBracket(start, self.index))
-                            },
-                            _ => {
-                                self.parenthesis_mismatch = true;
-                                self.parenthesis_mismatch_message = Box::new("Closing ']' without opening '['".to_string());
-                                None
-                            }
-                        }
                     },
-                    _ => {
-                        self.parenthesis_mismatch = true;
-                        self.parenthesis_mismatch_message = Box::new("Closing ']' without opening parentesis".to_string());
+                    None => {
+                        self.parenthesis_mismatch = Some(SyntaxError::UnmatchedDelimiter(span, ch1));
                         None
                     }
                 }
             },
-            '}' => {
-                match self.stack_elements.last() {
-                    Some(x) => {
-                        match &x {
-                            '{' => {
-                                self.stack_elements.pop();
+            '#' => {
+                self.advance();
+                match self.get_char() {
+                    '_' => {
+                        self.advance();
+                        Some(Symbols::Discard(start, self.position))
+                    },
+                    '?' => {
+                        self.advance();
+                        match self.get_char() {
+                            '@' => {
                                 self.advance();
-                                Some(Symbols::RightCurly(start, self.index))
+                                Some(Symbols::ReaderConditionalSplicing(start, self.position))
                             },
-                            _ => {
-                                self.parenthesis_mismatch_message = Box::new("Closing '}' without opening '{'".to_string());
-                                self.parenthesis_mismatch = true;
-                                None
-                            }
+                            _ => Some(Symbols::ReaderConditional(start, self.position))
                         }
                     },
-                    _ => {
-                        self.parenthesis_mismatch = true;
-                        self.parenthesis_mismatch_message = Box::new("Closing '}' without opening parentesis".to_string());
-                        None
-                    }
+                    _ => Some(Symbols::Hash(start, self.position))
                 }
             },
-            '#' => {
-                self.advance();
-                Some(Symbols::Hash(start, self.index))
-            },
-            '+' => {
+            '\'' => {
                 self.advance();
-                Some(Symbols::Plus(start, self.index))
+                Some(Symbols::Quote(start, self.position))
             },
-            '-' => {
+            '^' => {
                 self.advance();
-                Some(Symbols::Minus(start, self.index))
+                Some(Symbols::Caret(start, self.position))
             },
-            '*' => {
+            '`' => {
                 self.advance();
-                Some(Symbols::Mul(start, self.index))
+                Some(Symbols::SyntaxQuote(start, self.position))
             },
-            '/' => {
+            '~' => {
                 self.advance();
-                Some(Symbols::Divide(start, self.index))
-            },
-            '?' => {
-                self.advance();
-                Some(Symbols::Questionmark(start, self.index))
-            },
-            '<' => {
-                self.advance();
-                Some(Symbols::Less(start, self.index))
-            },
-            '>' => {
-                self.advance();
-                Some(Symbols::Greater(start, self.index))
-            },
-            '=' => {
-                self.advance();
-                Some(Symbols::Equal(start, self.index))
+                match self.get_char() {
+                    '@' => {
+                        self.advance();
+                        Some(Symbols::UnquoteSplicing(start, self.position))
+                    },
+                    _ => Some(Symbols::Unquote(start, self.position))
+                }
             },
             _ => None
         }
     }
 
-    fn is_reserved_keywords(&mut self, text: &str, start: u32, end: u32) -> Option<Symbols> {
-        match text {
-            "apply" => Some(Symbols::Apply(start, end)),
-            "def" => Some(Symbols::Def(start, end)),
-            "defn" => Some(Symbols::Defn(start, end)),
-            "defrecord" => Some(Symbols::Defrecord(start, end)),
-            "doc" => Some(Symbols::Doc(start, end)),
-            "fn" => Some(Symbols::Fn(start, end)),
-            "first" => Some(Symbols::First(start, end)),
-            "get" => Some(Symbols::Get(start, end)),
-            "if" => Some(Symbols::If(start, end)),
-            "let" => Some(Symbols::Let(start, end)),
-            "map" => Some(Symbols::Map(start, end)),
-            "name" => Some(Symbols::Name(start, end)),
-            "not" => {
-                match self.get_char() {
-                    '=' => {
-                        self.advance();
-                        Some(Symbols::NotEqual(start, self.index))
-                    },
-                    _ => Some(Symbols::LiteralName(start, end, Box::new(text.to_owned())))
+    fn is_number_literal(&mut self) -> Result<Option<Symbols>, SyntaxError> {
+        let ch = self.get_char();
+        let signed = (ch == '+' || ch == '-') && self.peek_char().is_ascii_digit();
+        if !ch.is_ascii_digit() && !signed {
+            return Ok(None)
+        }
+
+        let start = self.position;
+        let mut text = String::new();
+        while !is_terminating(self.get_char()) {
+            text.push(self.get_char());
+            self.advance();
+        }
+
+        parse_number(&text, start, self.position).map(Some)
+    }
+
+    fn is_string_literal(&mut self) -> Result<Option<Symbols>, SyntaxError> {
+        if self.get_char() != '"' {
+            return Ok(None)
+        }
+
+        let start = self.position;
+        let mut text = String::new();
+        self.advance();
+
+        loop {
+            let ch = self.get_char();
+            match ch {
+                '"' => {
+                    self.advance();
+                    return Ok(Some(Symbols::LiteralString(start, self.position, Box::new(text))))
+                },
+                '\0' => {
+                    return Err(SyntaxError::UnterminatedString(Span::new(start, self.position)))
+                },
+                '\\' => {
+                    let escape_start = self.position;
+                    self.advance();
+                    let escape = self.get_char();
+                    match escape {
+                        'n' => text.push('\n'),
+                        't' => text.push('\t'),
+                        'r' => text.push('\r'),
+                        'b' => text.push('\u{0008}'),
+                        'f' => text.push('\u{000C}'),
+                        '"' => text.push('"'),
+                        '\\' => text.push('\\'),
+                        'u' => {
+                            let mut digits = String::new();
+                            while digits.len() < 4 && self.peek_char().is_ascii_hexdigit() {
+                                self.advance();
+                                digits.push(self.get_char());
+                            }
+                            let code = match digits.len() {
+                                4 => u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32),
+                                _ => None
+                            };
+                            match code {
+                                Some(x) => text.push(x),
+                                None => {
+                                    self.advance();
+                                    return Err(SyntaxError::InvalidUnicodeEscape(Span::new(escape_start, self.position), Box::new(format!("u{}", digits))))
+                                }
+                            }
+                        },
+                        '0'..='7' => {
+                            let mut digits = String::from(escape);
+                            while digits.len() < 3 && matches!(self.peek_char(), '0'..='7') {
+                                self.advance();
+                                digits.push(self.get_char());
+                            }
+                            match u32::from_str_radix(&digits, 8).ok().filter(|x| *x <= 0o377).and_then(char::from_u32) {
+                                Some(x) => text.push(x),
+                                None => {
+                                    self.advance();
+                                    return Err(SyntaxError::InvalidOctalEscape(Span::new(escape_start, self.position), Box::new(digits)))
+                                }
+                            }
+                        },
+                        '\0' => {
+                            return Err(SyntaxError::UnterminatedString(Span::new(start, self.position)))
+                        },
+                        _ => {
+                            self.advance();
+                            return Err(SyntaxError::UnsupportedEscape(Span::new(escape_start, self.position), escape))
+                        }
+                    }
+                    self.advance()
+                },
+                _ => {
+                    text.push(ch);
+                    self.advance()
                 }
             }
-            "require" => Some(Symbols::Require(start, end)),
-            "second" => Some(Symbols::Second(start, end)),
-            "when" => Some(Symbols::When(start, end)),
-            _ => {
-                match (&text.starts_with(':'), text.len() == (1 as usize)) {
-                    (true, true) => None,
-                    (true, false) => {
-                        Some(Symbols::LiteralKeyword(start, end, Box::new(text.to_owned())))
-                    },
-                    _ => Some(Symbols::LiteralName(start, end, Box::new(text.to_owned())))
+        }
+    }
+
+    /* Pattern text is kept as written, a backslash only stops the next '"' from ending the literal */
+    fn is_regex_literal(&mut self) -> Result<Option<Symbols>, SyntaxError> {
+        if self.get_char() != '#' || self.peek_char() != '"' {
+            return Ok(None)
+        }
+
+        let start = self.position;
+        let mut text = String::new();
+        self.advance();
+        self.advance();
+
+        loop {
+            let ch = self.get_char();
+            match ch {
+                '"' => {
+                    self.advance();
+                    return Ok(Some(Symbols::LiteralRegex(start, self.position, Box::new(text))))
+                },
+                '\0' => return Err(SyntaxError::UnterminatedRegex(Span::new(start, self.position))),
+                '\\' => {
+                    text.push(ch);
+                    self.advance();
+                    if self.get_char() == '\0' {
+                        return Err(SyntaxError::UnterminatedRegex(Span::new(start, self.position)))
+                    }
+                    text.push(self.get_char());
+                    self.advance()
+                },
+                _ => {
+                    text.push(ch);
+                    self.advance()
                 }
             }
         }
     }
 
-    fn get_symbol(&mut self) -> Result<Symbols, Box<String>> {
+    fn is_char_literal(&mut self) -> Result<Option<Symbols>, SyntaxError> {
+        if self.get_char() != '\\' {
+            return Ok(None)
+        }
+
+        let start = self.position;
+        self.advance();
+
+        /* The first character is always part of the literal, so \( and \; are valid */
+        let mut text = String::new();
+        match self.get_char() {
+            '\0' => return Err(SyntaxError::UnterminatedCharacter(Span::new(start, self.position))),
+            ch => {
+                text.push(ch);
+                self.advance()
+            }
+        }
+        while !is_terminating(self.get_char()) {
+            text.push(self.get_char());
+            self.advance();
+        }
+
+        let span = Span::new(start, self.position);
+        let mut chars = text.chars();
+        let first = chars.next().unwrap_or('\0');
+        let rest = chars.as_str();
+
+        let value = match text.as_str() {
+            _ if rest.is_empty() => Some(first),
+            "newline" => Some('\n'),
+            "space" => Some(' '),
+            "tab" => Some('\t'),
+            "backspace" => Some('\u{0008}'),
+            "formfeed" => Some('\u{000C}'),
+            "return" => Some('\r'),
+            _ if first == 'u' => {
+                match rest.len() == 4 && rest.chars().all(|x| x.is_ascii_hexdigit()) {
+                    true => match u32::from_str_radix(rest, 16).ok().and_then(char::from_u32) {
+                        Some(x) => Some(x),
+                        None => return Err(SyntaxError::InvalidCharacterConstant(span, Box::new(text)))
+                    },
+                    false => return Err(SyntaxError::InvalidUnicodeCharacter(span, Box::new(text)))
+                }
+            },
+            _ if first == 'o' => {
+                match rest.len() <= 3 && rest.chars().all(|x| matches!(x, '0'..='7')) {
+                    true => match u32::from_str_radix(rest, 8).ok().filter(|x| *x <= 0o377) {
+                        Some(x) => char::from_u32(x),
+                        None => return Err(SyntaxError::InvalidOctalEscape(span, Box::new(text)))
+                    },
+                    false => return Err(SyntaxError::InvalidOctalLength(span, Box::new(text)))
+                }
+            },
+            _ => None
+        };
+
+        match value {
+            Some(x) => Ok(Some(Symbols::LiteralChar(start, self.position, x))),
+            None => Err(SyntaxError::UnsupportedCharacter(span, Box::new(text)))
+        }
+    }
+
+    /* ##Inf, ##-Inf and ##NaN read as floating point values */
+    fn is_symbolic_value(&mut self) -> Result<Option<Symbols>, SyntaxError> {
+        if self.get_char() != '#' || self.peek_char() != '#' {
+            return Ok(None)
+        }
+
+        let start = self.position;
+        self.advance();
+        self.advance();
+        let mut text = String::new();
+        while !is_terminating(self.get_char()) {
+            text.push(self.get_char());
+            self.advance();
+        }
+
+        let value = match text.as_str() {
+            "Inf" => f64::INFINITY,
+            "-Inf" => f64::NEG_INFINITY,
+            "NaN" => f64::NAN,
+            _ => return Err(SyntaxError::UnknownSymbolicValue(Span::new(start, self.position), Box::new(text)))
+        };
+        Ok(Some(Symbols::LiteralFloat(start, self.position, value)))
+    }
+
+    fn is_symbol_or_keyword(&mut self) -> Result<Option<Symbols>, SyntaxError> {
+        let ch = self.get_char();
+        if is_terminating(ch) || ch.is_ascii_digit() || ch == '#' || ch == '\'' {
+            return Ok(None)
+        }
+
+        let start = self.position;
+        let mut text = String::new();
+        while !is_terminating(self.get_char()) {
+            text.push(self.get_char());
+            self.advance();
+        }
+        let end = self.position;
+        let invalid = || SyntaxError::InvalidToken(Span::new(start, end), Box::new(text.clone()));
 
-        /* Remove whitespace, lineshift and comments */
+        if let Some(keyword) = text.strip_prefix("::") {
+            let (namespace, name) = split_symbol(keyword).ok_or_else(invalid)?;
+            return Ok(Some(Symbols::LiteralAutoResolvedKeyword(start, end, namespace, name)))
+        }
+        if let Some(keyword) = text.strip_prefix(':') {
+            let (namespace, name) = split_symbol(keyword).ok_or_else(invalid)?;
+            return Ok(Some(Symbols::LiteralKeyword(start, end, namespace, name)))
+        }
+        match split_symbol(&text) {
+            Some((None, name)) if name.as_str() == "nil" => Ok(Some(Symbols::LiteralNil(start, end))),
+            Some((None, name)) if name.as_str() == "true" || name.as_str() == "false" => {
+                Ok(Some(Symbols::LiteralBoolean(start, end, name.as_str() == "true")))
+            },
+            Some((namespace, name)) if !name.starts_with(|x: char| x.is_ascii_digit()) => {
+                Ok(Some(Symbols::LiteralName(start, end, namespace, name)))
+            },
+            _ => Err(invalid())
+        }
+    }
+
+    fn get_symbol(&mut self) -> Result<Symbols, SyntaxError> {
+
+        /* Remove whitespace, commas, lineshift and comments */
         loop {
             let ch = self.get_char();
             match ch {
-                ' ' | '\t' => {
+                _ if is_whitespace(ch) => {
                     self.advance();
                     continue
                 },
-                '\r' | '\n' => {
-                    self.advance();
-                    continue
+                /* Shebang lines are comments so scripts can be run directly */
+                '#' if self.peek_char() == '!' => {
+                    loop {
+                        let ch = self.get_char();
+                        match ch {
+                            '\r' | '\n' | '\0' => break,
+                            _ => self.advance()
+                        }
+                    }
                 },
                 ';' => {
                     loop {
@@ -277,59 +584,372 @@ impl LexicalAnalyzerMethods for LexicalAnalyzer {
         }
 
         /*  Handle end of file */
-        match self.get_char() {
-            '\0' => return Ok(Symbols::EOF),
-            _ => () 
+        if self.get_char() == '\0' { return Ok(Symbols::EOF(self.position, self.position)) }
+
+        /* Check for strings */
+        if let Some(symb) = self.is_string_literal()? {
+            return Ok(symb)
         }
 
-        let start = self.index; /* Save start position for next symbol */
+        /* Check for regular expressions */
+        if let Some(symb) = self.is_regex_literal()? {
+            return Ok(symb)
+        }
+
+        /* Check for characters */
+        if let Some(symb) = self.is_char_literal()? {
+            return Ok(symb)
+        }
+
+        /* Check for numbers, must be done before operators because of the sign */
+        if let Some(symb) = self.is_number_literal()? {
+            return Ok(symb)
+        }
+
+        /* Check for symbolic values, must be done before operators because of the '#' */
+        if let Some(symb) = self.is_symbolic_value()? {
+            return Ok(symb)
+        }
 
         /* Check for operators or delimiters */
         let symbol = self.is_operator_or_delimiter();
 
-        match symbol {
-            Some(symb) => {
-                match self.parenthesis_mismatch {
-                    true => {
-                        self.parenthesis_mismatch = false;
-                        return Err(self.parenthesis_mismatch_message.to_owned())
-                    }
-                    _ => return Ok(symb)
-                }
-            }
-            None => ()
+        if let Some(error) = self.parenthesis_mismatch.take() {
+            return Err(error)
         }
 
-        /* Check for reserved keywords or literal names */
-        if self.get_char().is_alphabetic() || self.get_char() == '_' || self.get_char() == ':'  {
+        if let Some(symb) = symbol {
+            return Ok(symb)
+        }
 
-            let mut buffer = std::string::String::new();
-            buffer.push(self.get_char());
-            self.advance();
+        /* Check for literal names or keywords, special forms are recognized by the semantic analyzer */
+        if let Some(symb) = self.is_symbol_or_keyword()? {
+            return Ok(symb)
+        }
 
-            loop {
-                let _cur = self.get_char();
-                if _cur.is_alphanumeric() || _cur == '_' {
-                    buffer.push(_cur);
-                    self.advance();
-                    continue
-                }
-                break
-            }
+        let (start, ch) = (self.position, self.get_char());
+        self.advance();
+        Err(SyntaxError::IllegalCharacter(Span::new(start, self.position), ch))
+    }
+}
 
-            let res = self.is_reserved_keywords(buffer.as_str(), start, self.index);
+/* The closing delimiter expected for an opening one */
+fn closing_of(open: char) -> char {
+    match open {
+        '(' => ')',
+        '[' => ']',
+        _ => '}'
+    }
+}
 
-            return match res {
-                Some(x) => {
-                    Ok(x) /* Found reserved keyword or literals */
-                },
-                _ => {
-                    Err(Box::new("Found ':' but there is no keyword".to_string()))
-                }
+/* Split a symbol or keyword name into optional namespace and name, 'ns/name' and 'ns//' are qualified */
+#[allow(clippy::box_collection)]
+fn split_symbol(text: &str) -> Option<(Option<Box<String>>, Box<String>)> {
+    if text.is_empty() || text.starts_with(':') || text.ends_with(':') || text.contains("::") {
+        return None
+    }
+    if text == "/" {
+        return Some((None, Box::new(text.to_string())))
+    }
+
+    let (namespace, name) = match text.strip_suffix("//") {
+        Some(namespace) => (Some(namespace), "/"),
+        None => match text.rsplit_once('/') {
+            Some((namespace, name)) => (Some(namespace), name),
+            None => (None, text)
+        }
+    };
+
+    match namespace {
+        Some(namespace) => {
+            let valid = !namespace.is_empty() && !name.is_empty() && !namespace.ends_with(':')
+                && !namespace.starts_with(|x: char| x.is_ascii_digit());
+            match valid {
+                true => Some((Some(Box::new(namespace.to_string())), Box::new(name.to_string()))),
+                false => None
             }
+        },
+        None => Some((None, Box::new(name.to_string())))
+    }
+}
+
+/* Escape text so it can be printed back inside a string literal */
+fn escape_string(text: &str) -> String {
+    let mut result = String::new();
+    for ch in text.chars() {
+        match ch {
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            '\r' => result.push_str("\\r"),
+            '\u{0008}' => result.push_str("\\b"),
+            '\u{000C}' => result.push_str("\\f"),
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            _ => result.push(ch)
+        }
+    }
+    result
+}
+
+/* Whitespace as the Clojure reader sees it, Java's Character.isWhitespace plus the comma.
+ * Unlike char::is_whitespace that leaves out the no-break spaces and includes the separators U+001C to U+001F.
+ */
+fn is_whitespace(ch: char) -> bool {
+    match ch {
+        ',' | '\u{001C}'..='\u{001F}' => true,
+        '\u{0085}' | '\u{00A0}' | '\u{2007}' | '\u{202F}' => false,
+        _ => ch.is_whitespace()
+    }
+}
+
+/* Characters that end a token that is not a delimiter itself */
+fn is_terminating(ch: char) -> bool {
+    is_whitespace(ch) || matches!(ch, '\0' | '(' | ')' | '[' | ']' | '{' | '}' | '"' | ';' | '@' | '^' | '`' | '~' | '\\')
+}
+
+/* Parse a number token following the Clojure reader grammar for integers, ratios, floats and big decimals */
+fn parse_number(text: &str, start: Position, end: Position) -> Result<Symbols, SyntaxError> {
+    let span = Span::new(start, end);
+    let invalid = || SyntaxError::InvalidNumber(span, Box::new(text.to_string()));
+    let (negative, body) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text))
+    };
+
+    /* Ratio like 22/7, reduced to lowest terms before it is narrowed, parts that do not fit a long make a big ratio */
+    if let Some((numerator, denominator)) = body.split_once('/') {
+        if !is_decimal_digits(numerator) || !is_decimal_digits(denominator) {
+            return Err(invalid())
+        }
+        let (numerator, denominator) = (trim_zeros(numerator), trim_zeros(denominator));
+        if denominator == "0" {
+            return Err(SyntaxError::DivideByZero(span, Box::new(text.to_string())))
+        }
+        let divisor = greatest_common_divisor(&numerator, &denominator);
+        let (mut numerator, denominator) = (divide_decimal(&numerator, &divisor).0, divide_decimal(&denominator, &divisor).0);
+        if negative && numerator != "0" {
+            numerator.insert(0, '-')
+        }
+        return match (numerator.parse::<i64>(), denominator.parse::<i64>()) {
+            (Ok(x), Ok(1)) => Ok(Symbols::LiteralInteger(start, end, x)),
+            (Ok(x), Ok(y)) => Ok(Symbols::LiteralRatio(start, end, x, y)),
+            (Err(_), Ok(1)) => Ok(Symbols::LiteralBigInteger(start, end, Box::new(numerator))),
+            _ => Ok(Symbols::LiteralBigRatio(start, end, Box::new(numerator), Box::new(denominator)))
+        }
+    }
+
+    /* Big decimal with 'M' suffix, integers are allowed as well */
+    if let Some(decimal) = body.strip_suffix('M') {
+        if !is_float_digits(decimal) {
+            return Err(invalid())
+        }
+        let sign = if negative { "-" } else { "" };
+        return Ok(Symbols::LiteralBigDecimal(start, end, Box::new(format!("{}{}", sign, decimal))))
+    }
+
+    /* Floating point with fraction and or exponent */
+    if body.contains(['.', 'e', 'E']) && !body.contains(['x', 'X', 'r', 'R']) {
+        if !is_float_digits(body) {
+            return Err(invalid())
+        }
+        return match text.parse::<f64>() {
+            Ok(x) => Ok(Symbols::LiteralFloat(start, end, x)),
+            _ => Err(invalid())
+        }
+    }
+
+    /* Integers, 'N' suffix forces a big integer */
+    let (big, body) = match body.strip_suffix('N') {
+        Some(rest) => (true, rest),
+        None => (false, body)
+    };
+
+    let (radix, digits) = if body == "0" {
+        (10, body)
+    }
+    else if let Some(hex) = body.strip_prefix("0x").or_else(|| body.strip_prefix("0X")) {
+        (16, hex)
+    }
+    else if let Some(octal) = body.strip_prefix('0') {
+        (8, octal)
+    }
+    else if let Some((radix, digits)) = body.split_once(['r', 'R']) {
+        match (is_decimal_digits(radix), radix.len() <= 2) {
+            (true, true) => (radix.parse::<u32>().map_err(|_| invalid())?, digits),
+            _ => return Err(invalid())
+        }
+    }
+    else {
+        (10, body)
+    };
+
+    if !(2..=36).contains(&radix) {
+        return Err(SyntaxError::RadixOutOfRange(span, Box::new(text.to_string())))
+    }
+
+    let mut value = match to_decimal_digits(digits, radix) {
+        Some(x) => x,
+        None => return Err(invalid())
+    };
+    if negative && value != "0" {
+        value.insert(0, '-')
+    }
+
+    match (big, value.parse::<i64>()) {
+        (false, Ok(x)) => Ok(Symbols::LiteralInteger(start, end, x)),
+        _ => Ok(Symbols::LiteralBigInteger(start, end, Box::new(value)))
+    }
+}
+
+fn is_decimal_digits(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|x| x.is_ascii_digit())
+}
+
+/* Matches [0-9]+(\.[0-9]*)?([eE][-+]?[0-9]+)? */
+fn is_float_digits(text: &str) -> bool {
+    let (mantissa, exponent) = match text.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, Some(exponent)),
+        None => (text, None)
+    };
+    let (integer, fraction) = match mantissa.split_once('.') {
+        Some((integer, fraction)) => (integer, fraction),
+        None => (mantissa, "")
+    };
+    let exponent_ok = match exponent {
+        Some(x) => is_decimal_digits(x.strip_prefix(['+', '-']).unwrap_or(x)),
+        None => true
+    };
+
+    is_decimal_digits(integer) && (fraction.is_empty() || is_decimal_digits(fraction)) && exponent_ok
+}
+
+/* Convert digits in any radix to a decimal string without size limit */
+fn to_decimal_digits(digits: &str, radix: u32) -> Option<String> {
+    if digits.is_empty() {
+        return None
+    }
+
+    let mut result : Vec<u32> = vec![0]; /* Least significant digit first */
+    for ch in digits.chars() {
+        let mut carry = ch.to_digit(radix)?;
+        for digit in result.iter_mut() {
+            let x = *digit * radix + carry;
+            *digit = x % 10;
+            carry = x / 10;
+        }
+        while carry > 0 {
+            result.push(carry % 10);
+            carry /= 10;
+        }
+    }
+    while result.len() > 1 && result.last() == Some(&0) {
+        result.pop();
+    }
+
+    Some(result.iter().rev().map(|x| char::from_digit(*x, 10).unwrap_or('0')).collect())
+}
+
+/* Decimal digits without leading zeros, "0" when nothing is left */
+fn trim_zeros(digits: &str) -> String {
+    match digits.trim_start_matches('0') {
+        "" => "0".to_string(),
+        x => x.to_string()
+    }
+}
+
+/* Compare decimal strings without leading zeros by value */
+fn compare_decimal(a: &str, b: &str) -> std::cmp::Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/* Subtract decimal strings without size limit, a must not be smaller than b */
+fn subtract_decimal(a: &str, b: &str) -> String {
+    let mut result : Vec<u8> = Vec::new(); /* Least significant digit first */
+    let mut borrow = 0;
+    let mut b = b.bytes().rev();
+    for x in a.bytes().rev() {
+        let y = b.next().map(|y| y - b'0').unwrap_or(0) + borrow;
+        let x = x - b'0';
+        borrow = u8::from(x < y);
+        result.push(b'0' + x + 10 * borrow - y);
+    }
+    result.reverse();
+    trim_zeros(&String::from_utf8_lossy(&result))
+}
+
+/* Long division of decimal strings without size limit, gives the quotient and the remainder */
+fn divide_decimal(dividend: &str, divisor: &str) -> (String, String) {
+    let mut quotient = String::new();
+    let mut remainder = "0".to_string();
+    for ch in dividend.chars() {
+        remainder = trim_zeros(&format!("{}{}", remainder, ch));
+        let mut digit = 0;
+        while compare_decimal(&remainder, divisor).is_ge() {
+            remainder = subtract_decimal(&remainder, divisor);
+            digit += 1;
         }
+        quotient.push(char::from_digit(digit, 10).unwrap_or('0'));
+    }
+    (trim_zeros(&quotient), remainder)
+}
 
-        Err(Box::new("Illegal character found in text!".to_string()))
+fn greatest_common_divisor(a: &str, b: &str) -> String {
+    match b {
+        "0" => a.to_string(),
+        _ => greatest_common_divisor(b, &divide_decimal(a, b).1)
+    }
+}
+
+impl std::fmt::Display for Symbols {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Symbols::EOF(_, _) => "",
+            Symbols::LeftParen(_, _) => "(",
+            Symbols::RightParen(_, _) => ")",
+            Symbols::LeftBracket(_, _) => "[",
+            Symbols::RightBracket(_, _) => "]",
+            Symbols::LeftCurly(_, _) => "{",
+            Symbols::RightCurly(_, _) => "}",
+            Symbols::Hash(_, _) => "#",
+            Symbols::Quote(_, _) => "'",
+            Symbols::SyntaxQuote(_, _) => "`",
+            Symbols::Unquote(_, _) => "~",
+            Symbols::UnquoteSplicing(_, _) => "~@",
+            Symbols::Discard(_, _) => "#_",
+            Symbols::Caret(_, _) => "^",
+            Symbols::ReaderConditional(_, _) => "#?",
+            Symbols::ReaderConditionalSplicing(_, _) => "#?@",
+            Symbols::LiteralNil(_, _) => "nil",
+            Symbols::LiteralBoolean(_, _, value) => return write!(f, "{}", value),
+            Symbols::LiteralName(_, _, Some(namespace), name) => return write!(f, "{}/{}", namespace, name),
+            Symbols::LiteralName(_, _, None, name) => name.as_str(),
+            Symbols::LiteralKeyword(_, _, Some(namespace), name) => return write!(f, ":{}/{}", namespace, name),
+            Symbols::LiteralKeyword(_, _, None, name) => return write!(f, ":{}", name),
+            Symbols::LiteralAutoResolvedKeyword(_, _, Some(namespace), name) => return write!(f, "::{}/{}", namespace, name),
+            Symbols::LiteralAutoResolvedKeyword(_, _, None, name) => return write!(f, "::{}", name),
+            Symbols::LiteralInteger(_, _, value) => return write!(f, "{}", value),
+            Symbols::LiteralBigInteger(_, _, value) => return write!(f, "{}N", value),
+            Symbols::LiteralRatio(_, _, numerator, denominator) => return write!(f, "{}/{}", numerator, denominator),
+            Symbols::LiteralBigRatio(_, _, numerator, denominator) => return write!(f, "{}/{}", numerator, denominator),
+            Symbols::LiteralFloat(_, _, value) if value.is_nan() => "##NaN",
+            Symbols::LiteralFloat(_, _, value) if value.is_infinite() => if *value > 0.0 { "##Inf" } else { "##-Inf" },
+            Symbols::LiteralFloat(_, _, value) => return write!(f, "{:?}", value),
+            Symbols::LiteralBigDecimal(_, _, value) => return write!(f, "{}M", value),
+            Symbols::LiteralString(_, _, text) => return write!(f, "\"{}\"", escape_string(text)),
+            Symbols::LiteralRegex(_, _, text) => return write!(f, "#\"{}\"", text),
+            Symbols::LiteralChar(_, _, ch) => {
+                return match ch {
+                    '\n' => write!(f, "\\newline"),
+                    ' ' => write!(f, "\\space"),
+                    '\t' => write!(f, "\\tab"),
+                    '\u{0008}' => write!(f, "\\backspace"),
+                    '\u{000C}' => write!(f, "\\formfeed"),
+                    '\r' => write!(f, "\\return"),
+                    _ => write!(f, "\\{}", ch)
+                }
+            }
+        };
+        write!(f, "{}", text)
     }
 }
 
@@ -339,10 +959,19 @@ impl LexicalAnalyzerMethods for LexicalAnalyzer {
 ///////////////////////////////////////////////////////////////////////////////
 
 
+/* The oldest tests spell out every match arm with assert!(true) and assert!(false) */
 #[cfg(test)]
+#[allow(clippy::assertions_on_constants)]
 mod tests {
 
     use crate::parser::lexical_analyzer::{Symbols, LexicalAnalyzerMethods, LexicalAnalyzer};
+    use crate::parser::source_position::{Position, Span};
+    use crate::parser::syntax_error::SyntaxError;
+
+    /* Position on the first line of the default file */
+    fn p(offset: u32) -> Position {
+        Position::new(0, 1, offset + 1, offset)
+    }
 
     // Tests for operators and delimiters /////////////////////////////////////
 
@@ -354,7 +983,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftParen(2, 3) => assert!(true),
+                    Symbols::LeftParen(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -370,7 +999,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftBracket(2, 3) => assert!(true),
+                    Symbols::LeftBracket(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -386,7 +1015,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftCurly(2, 3) => assert!(true),
+                    Symbols::LeftCurly(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -402,7 +1031,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftParen(2, 3) => assert!(true),
+                    Symbols::LeftParen(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -412,7 +1041,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::RightParen(3, 4) => assert!(true),
+                    Symbols::RightParen(start, end) if start == p(3) && end == p(4) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -428,7 +1057,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftBracket(2, 3) => assert!(true),
+                    Symbols::LeftBracket(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -438,7 +1067,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::RightBracket(3, 4) => assert!(true),
+                    Symbols::RightBracket(start, end) if start == p(3) && end == p(4) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -454,7 +1083,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftCurly(2, 3) => assert!(true),
+                    Symbols::LeftCurly(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -464,7 +1093,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::RightCurly(3, 4) => assert!(true),
+                    Symbols::RightCurly(start, end) if start == p(3) && end == p(4) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -480,7 +1109,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftParen(0, 1) => assert!(true),
+                    Symbols::LeftParen(start, end) if start == p(0) && end == p(1) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -490,7 +1119,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftBracket(1, 2) => assert!(true),
+                    Symbols::LeftBracket(start, end) if start == p(1) && end == p(2) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -500,7 +1129,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LeftCurly(2, 3) => assert!(true),
+                    Symbols::LeftCurly(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -510,7 +1139,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::RightCurly(3, 4) => assert!(true),
+                    Symbols::RightCurly(start, end) if start == p(3) && end == p(4) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -520,7 +1149,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::RightBracket(4, 5) => assert!(true),
+                    Symbols::RightBracket(start, end) if start == p(4) && end == p(5) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -530,7 +1159,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::RightParen(5, 6) => assert!(true),
+                    Symbols::RightParen(start, end) if start == p(5) && end == p(6) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -546,7 +1175,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Hash(2, 3) => assert!(true),
+                    Symbols::Hash(start, end) if start == p(2) && end == p(3) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -554,6 +1183,82 @@ mod tests {
         }
     }
 
+    #[test]
+    fn operator_or_delimiter_discard() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("#_a #'b"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::Discard(p(0), p(2))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(2), p(3), None, Box::new("a".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::Hash(p(4), p(5))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::Quote(p(5), p(6))))
+    }
+
+    #[test]
+    fn operator_or_delimiter_reader_conditional() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("#?(#?@("));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::ReaderConditional(p(0), p(2))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(2), p(3))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::ReaderConditionalSplicing(p(3), p(6))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(6), p(7))))
+    }
+
+    #[test]
+    fn operator_or_delimiter_quotes() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("'a `b ~c ~@d a' ^e"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::Quote(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(1), p(2), None, Box::new("a".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::SyntaxQuote(p(3), p(4))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(4), p(5), None, Box::new("b".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::Unquote(p(6), p(7))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(7), p(8), None, Box::new("c".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::UnquoteSplicing(p(9), p(11))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(11), p(12), None, Box::new("d".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(13), p(15), None, Box::new("a'".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::Caret(p(16), p(17))))
+    }
+
+    #[test]
+    fn operator_or_delimiter_mismatch() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("([ ) }"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftBracket(p(1), p(2))));
+        assert_eq!(lexer.get_symbol(), Err(SyntaxError::MismatchedDelimiter(Span::new(p(3), p(4)), ')', ']', Span::new(p(1), p(2)))));
+        assert_eq!(lexer.open_delimiters(), &[('(', Span::new(p(0), p(1))), ('[', Span::new(p(1), p(2)))]);
+        assert_eq!(LexicalAnalyzer::new("}").get_symbol(), Err(SyntaxError::UnmatchedDelimiter(Span::new(p(0), p(1)), '}')))
+    }
+
+    #[test]
+    fn closing_suggestion_from_indentation() {
+
+        let text = "(defn f [x]\n  (let [y 1]\n    (+ x y)\n\n  ; note\n(defn g []\n  2)\n";
+        let mut lexer = Box::new(LexicalAnalyzer::new(text));
+        while !matches!(lexer.get_symbol(), Ok(Symbols::EOF(_, _))) {}
+        let end = Span::new(Position::new(0, 8, 1, 62), Position::new(0, 8, 1, 62));
+
+        assert_eq!(lexer.missing_closing_delimiter(end), SyntaxError::MissingClosingDelimiter(end, ')',
+            Span::new(Position::new(0, 2, 3, 14), Position::new(0, 2, 4, 15)), Some(Position::new(0, 3, 12, 36))));
+        assert_eq!(lexer.missing_closing_delimiter(end), SyntaxError::MissingClosingDelimiter(end, ')',
+            Span::new(p(0), p(1)), Some(Position::new(0, 3, 12, 36))));
+        assert_eq!(lexer.missing_closing_delimiter(end), SyntaxError::UnexpectedEndOfFile(end));
+        assert_eq!(LexicalAnalyzer::new("[a\n b").closing_suggestion(Span::new(p(0), p(1))), None)
+    }
+
+    #[test]
+    fn illegal_character() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(" @x"));
+
+        assert_eq!(lexer.get_symbol(), Err(SyntaxError::IllegalCharacter(Span::new(p(1), p(2)), '@')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(2), p(3), None, Box::new("x".to_string()))))
+    }
+
     #[test]
     fn operator_or_delimiter_plus() {
 
@@ -562,7 +1267,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Plus(2, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            "+" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -578,7 +1288,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Minus(2, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            "-" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -594,7 +1309,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Mul(2, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            "*" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -610,7 +1330,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Divide(2, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            "/" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -626,7 +1351,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Questionmark(2, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            "?" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -642,7 +1372,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Less(2, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            "<" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -658,7 +1393,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Greater(2, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            ">" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -667,23 +1407,78 @@ mod tests {
     }
 
     #[test]
-    fn operator_or_delimiter_equal() {
+    fn operator_or_delimiter_equal() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("  ="));
+
+        match lexer.get_symbol() { 
+            Ok(x) => {
+                match x {
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(3) => {
+                        match text.as_str() {
+                            "=" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
+                    _ => assert!(false)
+                }
+            },
+            _ => assert!(false)
+        }
+    }
+
+
+    #[test]
+    fn operator_characters_inside_symbols() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("(>= -main *ns* -1 - 1 clojure.core/+)"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(1), p(3), None, Box::new(">=".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(4), p(9), None, Box::new("-main".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(10), p(14), None, Box::new("*ns*".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(15), p(17), -1)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(18), p(19), None, Box::new("-".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(20), p(21), 1)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(22), p(36), Some(Box::new("clojure.core".to_string())), Box::new("+".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightParen(p(36), p(37))))
+    }
+
+
+    #[test]
+    fn nil_and_booleans() {
 
-        let mut lexer = Box::new(LexicalAnalyzer::new("  ="));
+        let mut lexer = Box::new(LexicalAnalyzer::new("nil true false nil? user/true :nil"));
 
-        match lexer.get_symbol() { 
-            Ok(x) => {
-                match x {
-                    Symbols::Equal(2, 3) => assert!(true),
-                    _ => assert!(false)
-                }
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralNil(p(0), p(3))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBoolean(p(4), p(8), true)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBoolean(p(9), p(14), false)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(15), p(19), None, Box::new("nil?".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(20), p(29), Some(Box::new("user".to_string())), Box::new("true".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralKeyword(p(30), p(34), None, Box::new("nil".to_string()))))
+    }
+
+    #[test]
+    fn symbolic_values() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("##Inf [##-Inf ##NaN] ##Foo"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralFloat(p(0), p(5), f64::INFINITY)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftBracket(p(6), p(7))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralFloat(p(7), p(13), f64::NEG_INFINITY)));
+        match lexer.get_symbol() {
+            Ok(x @ Symbols::LiteralFloat(_, _, value)) if value.is_nan() => {
+                assert_eq!(x.span(), Span::new(p(14), p(19)));
+                assert_eq!(x.to_string(), "##NaN")
             },
-            _ => assert!(false)
+            x => panic!("Unexpected result {:?}", x)
         }
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightBracket(p(19), p(20))));
+        assert_eq!(lexer.get_symbol(), Err(SyntaxError::UnknownSymbolicValue(Span::new(p(21), p(26)), Box::new("Foo".to_string()))))
     }
 
 
-    // Tests reserved keywords ////////////////////////////////////////////////
+    // Tests former reserved keywords, now plain names ///////////////////////
 
     #[test]
     fn keyword_apply() {
@@ -693,7 +1488,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Apply(0, 5) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(5) => {
+                        match text.as_str() {
+                            "apply" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -710,7 +1510,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Def(2, 5) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(2) && end == p(5) => {
+                        match text.as_str() {
+                            "def" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -726,7 +1531,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Defn(0, 4) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(4) => {
+                        match text.as_str() {
+                            "defn" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -742,7 +1552,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Defrecord(0, 9) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(9) => {
+                        match text.as_str() {
+                            "defrecord" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -758,7 +1573,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Doc(0, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(3) => {
+                        match text.as_str() {
+                            "doc" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -774,7 +1594,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Fn(0, 2) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(2) => {
+                        match text.as_str() {
+                            "fn" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -790,7 +1615,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::First(0, 5) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(5) => {
+                        match text.as_str() {
+                            "first" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -806,7 +1636,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Get(0, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(3) => {
+                        match text.as_str() {
+                            "get" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -822,7 +1657,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::If(0, 2) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(2) => {
+                        match text.as_str() {
+                            "if" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -838,7 +1678,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Let(0, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(3) => {
+                        match text.as_str() {
+                            "let" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -854,7 +1699,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Map(0, 3) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(3) => {
+                        match text.as_str() {
+                            "map" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -870,7 +1720,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Name(0, 4) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(4) => {
+                        match text.as_str() {
+                            "name" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -886,7 +1741,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Require(0, 7) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(7) => {
+                        match text.as_str() {
+                            "require" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -902,7 +1762,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::Second(0, 6) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(6) => {
+                        match text.as_str() {
+                            "second" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -918,7 +1783,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::When(0, 4) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(4) => {
+                        match text.as_str() {
+                            "when" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -934,7 +1804,12 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::NotEqual(0, 4) => assert!(true),
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(4) => {
+                        match text.as_str() {
+                            "not=" => assert!(true),
+                            _ => assert!(false)
+                        }
+                    },
                     _ => assert!(false)
                 }
             },
@@ -950,7 +1825,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::LiteralName(0, 3, text ) => {
+                    Symbols::LiteralName(start, end, None, text) if start == p(0) && end == p(3) => {
                         match text.as_str() {
                             "not" => assert!(true),
                             _ => assert!(false)
@@ -965,8 +1840,380 @@ mod tests {
 
 
 
+    // Tests for symbols and keywords ///////////////////////////////////////
+
+    #[test]
+    fn symbol_with_special_characters() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("swap! empty? ->> *earmuffs* <= a.b$c%&|' foo#"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(0), p(5), None, Box::new("swap!".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(6), p(12), None, Box::new("empty?".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(13), p(16), None, Box::new("->>".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(17), p(27), None, Box::new("*earmuffs*".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(28), p(30), None, Box::new("<=".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(31), p(40), None, Box::new("a.b$c%&|'".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(41), p(45), None, Box::new("foo#".to_string()))))
+    }
+
+    #[test]
+    fn symbol_namespace_qualified() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("clojure.string/join clojure.core// a/b/c 1/x"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(0), p(19), Some(Box::new("clojure.string".to_string())), Box::new("join".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(20), p(34), Some(Box::new("clojure.core".to_string())), Box::new("/".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(35), p(40), Some(Box::new("a/b".to_string())), Box::new("c".to_string()))));
+        assert_eq!(lexer.get_symbol(), Err(SyntaxError::InvalidNumber(Span::new(p(41), p(44)), Box::new("1/x".to_string()))))
+    }
+
+    #[test]
+    fn symbol_terminated_by_delimiter() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("(inc! x)"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(1), p(5), None, Box::new("inc!".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(6), p(7), None, Box::new("x".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightParen(p(7), p(8))))
+    }
+
+    #[test]
+    fn keyword_simple_and_qualified() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(":foo :my.ns/bar :valid?"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralKeyword(p(0), p(4), None, Box::new("foo".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralKeyword(p(5), p(15), Some(Box::new("my.ns".to_string())), Box::new("bar".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralKeyword(p(16), p(23), None, Box::new("valid?".to_string()))))
+    }
+
+    #[test]
+    fn keyword_auto_resolved() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("::foo ::str/bar"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralAutoResolvedKeyword(p(0), p(5), None, Box::new("foo".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralAutoResolvedKeyword(p(6), p(15), Some(Box::new("str".to_string())), Box::new("bar".to_string()))))
+    }
+
+    #[test]
+    fn keyword_invalid() {
+
+        assert_eq!(LexicalAnalyzer::new(":").get_symbol(), Err(SyntaxError::InvalidToken(Span::new(p(0), p(1)), Box::new(":".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(":::foo").get_symbol(), Err(SyntaxError::InvalidToken(Span::new(p(0), p(6)), Box::new(":::foo".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(":foo:").get_symbol(), Err(SyntaxError::InvalidToken(Span::new(p(0), p(5)), Box::new(":foo:".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(":foo/").get_symbol(), Err(SyntaxError::InvalidToken(Span::new(p(0), p(5)), Box::new(":foo/".to_string()))))
+    }
+
+
+
+    // Tests for numeric literals //////////////////////////////////////////
+
+    #[test]
+    fn number_integer() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("  42 0 -7 +3"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(2), p(4), 42)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(5), p(6), 0)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(7), p(9), -7)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(10), p(12), 3)))
+    }
+
+    #[test]
+    fn number_hex_octal_and_radix() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("0xFF -0x10 017 2r1010 36rZZ 16rEE"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(0), p(4), 255)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(5), p(10), -16)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(11), p(14), 15)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(15), p(21), 10)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(22), p(27), 1295)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(28), p(33), 238)))
+    }
+
+    #[test]
+    fn number_big_integer() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("12N 99999999999999999999 -0xFFFFFFFFFFFFFFFFFF"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBigInteger(p(0), p(3), Box::new("12".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBigInteger(p(4), p(24), Box::new("99999999999999999999".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBigInteger(p(25), p(46), Box::new("-4722366482869645213695".to_string()))))
+    }
+
+    #[test]
+    fn number_ratio() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("22/7 -4/6 4/2"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralRatio(p(0), p(4), 22, 7)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralRatio(p(5), p(9), -2, 3)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(10), p(13), 2)))
+    }
+
+    #[test]
+    fn number_big_ratio() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("99999999999999999999/3 -99999999999999999999/7 4/18446744073709551616 36893488147419103232/18446744073709551616 0/00"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBigInteger(p(0), p(22), Box::new("33333333333333333333".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBigRatio(p(23), p(46), Box::new("-99999999999999999999".to_string()), Box::new("7".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralRatio(p(47), p(69), 1, 4611686018427387904)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(70), p(111), 2)));
+        assert_eq!(lexer.get_symbol(), Err(SyntaxError::DivideByZero(Span::new(p(112), p(116)), Box::new("0/00".to_string()))))
+    }
+
+    #[test]
+    fn number_float() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("2.75 -2.5e3 1E-2 5."));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralFloat(p(0), p(4), 2.75)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralFloat(p(5), p(11), -2500.0)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralFloat(p(12), p(16), 0.01)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralFloat(p(17), p(19), 5.0)))
+    }
+
+    #[test]
+    fn number_big_decimal() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("1.50M -3M"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBigDecimal(p(0), p(5), Box::new("1.50".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralBigDecimal(p(6), p(9), Box::new("-3".to_string()))))
+    }
+
+    #[test]
+    fn number_inside_list() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("(+ 1 2)"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(1), p(2), None, Box::new("+".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(3), p(4), 1)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(5), p(6), 2)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightParen(p(6), p(7))))
+    }
+
+    #[test]
+    fn number_invalid() {
+
+        assert_eq!(LexicalAnalyzer::new("08").get_symbol(), Err(SyntaxError::InvalidNumber(Span::new(p(0), p(2)), Box::new("08".to_string()))));
+        assert_eq!(LexicalAnalyzer::new("12abc").get_symbol(), Err(SyntaxError::InvalidNumber(Span::new(p(0), p(5)), Box::new("12abc".to_string()))));
+        assert_eq!(LexicalAnalyzer::new("1/0").get_symbol(), Err(SyntaxError::DivideByZero(Span::new(p(0), p(3)), Box::new("1/0".to_string()))));
+        assert_eq!(LexicalAnalyzer::new("40r1").get_symbol(), Err(SyntaxError::RadixOutOfRange(Span::new(p(0), p(4)), Box::new("40r1".to_string()))))
+    }
+
+
+
+    // Tests for string literals ///////////////////////////////////////////
+
+    #[test]
+    fn string_simple() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("  \"hello world\" \"\""));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralString(p(2), p(15), Box::new("hello world".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralString(p(16), p(18), Box::new("".to_string()))))
+    }
+
+    #[test]
+    fn string_escapes() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(r#""a\n\t\r\b\f\"\\b""#));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralString(p(0), p(18), Box::new("a\n\t\r\u{8}\u{c}\"\\b".to_string()))))
+    }
+
+    #[test]
+    fn string_unicode_and_octal_escapes() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(r#""\u03A9\101\7\0""#));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralString(p(0), p(16), Box::new("\u{3A9}A\u{7}\u{0}".to_string()))))
+    }
+
+    #[test]
+    fn string_multi_line() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("(\"first\nsecond\")"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralString(p(1), Position::new(0, 2, 8, 15), Box::new("first\nsecond".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightParen(Position::new(0, 2, 8, 15), Position::new(0, 2, 9, 16))))
+    }
+
+    #[test]
+    fn string_unterminated() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("(  \"abc\ndef)"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Err(SyntaxError::UnterminatedString(Span::new(p(3), Position::new(0, 2, 5, 12)))))
+    }
+
+    #[test]
+    fn regex_literal() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(r#"#"a\"b\d" #"x"#));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralRegex(p(0), p(9), Box::new(r#"a\"b\d"#.to_string()))));
+        assert_eq!(lexer.get_symbol(), Err(SyntaxError::UnterminatedRegex(Span::new(p(10), p(13)))))
+    }
+
+    #[test]
+    fn string_invalid_escapes() {
+
+        assert_eq!(LexicalAnalyzer::new(r#""\q""#).get_symbol(), Err(SyntaxError::UnsupportedEscape(Span::new(p(1), p(3)), 'q')));
+        assert_eq!(LexicalAnalyzer::new(r#""\u12""#).get_symbol(), Err(SyntaxError::InvalidUnicodeEscape(Span::new(p(1), p(5)), Box::new("u12".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(r#""\400""#).get_symbol(), Err(SyntaxError::InvalidOctalEscape(Span::new(p(1), p(5)), Box::new("400".to_string()))))
+    }
+
+
+
+    // Tests for character literals ////////////////////////////////////////
+
+    #[test]
+    fn char_single() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(r"\a \Ω \( \;"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(0), p(2), 'a')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(3), Position::new(0, 1, 6, 6), 'Ω')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(Position::new(0, 1, 7, 7), Position::new(0, 1, 9, 9), '(')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(Position::new(0, 1, 10, 10), Position::new(0, 1, 12, 12), ';')))
+    }
+
+    #[test]
+    fn char_named() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(r"\newline \space \tab \backspace \formfeed \return"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(0), p(8), '\n')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(9), p(15), ' ')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(16), p(20), '\t')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(21), p(31), '\u{8}')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(32), p(41), '\u{c}')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(42), p(49), '\r')))
+    }
+
+    #[test]
+    fn char_unicode_and_octal() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new(r"[\u03A9 \o101 \u \o]"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftBracket(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(1), p(7), 'Ω')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(8), p(13), 'A')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(14), p(16), 'u')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralChar(p(17), p(19), 'o')));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightBracket(p(19), p(20))))
+    }
+
+    #[test]
+    fn char_invalid() {
+
+        assert_eq!(LexicalAnalyzer::new(r"\foo").get_symbol(), Err(SyntaxError::UnsupportedCharacter(Span::new(p(0), p(4)), Box::new("foo".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(r"\u12").get_symbol(), Err(SyntaxError::InvalidUnicodeCharacter(Span::new(p(0), p(4)), Box::new("u12".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(r"\uD800").get_symbol(), Err(SyntaxError::InvalidCharacterConstant(Span::new(p(0), p(6)), Box::new("uD800".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(r"\o400").get_symbol(), Err(SyntaxError::InvalidOctalEscape(Span::new(p(0), p(5)), Box::new("o400".to_string()))));
+        assert_eq!(LexicalAnalyzer::new(r"\").get_symbol(), Err(SyntaxError::UnterminatedCharacter(Span::new(p(0), p(1)))))
+    }
+
+
+
+    // Tests for source positions //////////////////////////////////////////
+
+    #[test]
+    fn position_line_and_column() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("(a\r\n  :b)\n\"λ\" c"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(1), p(2), None, Box::new("a".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralKeyword(Position::new(0, 2, 3, 6), Position::new(0, 2, 5, 8), None, Box::new("b".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightParen(Position::new(0, 2, 5, 8), Position::new(0, 2, 6, 9))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralString(Position::new(0, 3, 1, 10), Position::new(0, 3, 4, 14), Box::new("λ".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(Position::new(0, 3, 5, 15), Position::new(0, 3, 6, 16), None, Box::new("c".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::EOF(Position::new(0, 3, 6, 16), Position::new(0, 3, 6, 16))))
+    }
+
+    #[test]
+    fn position_with_file_id() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new_with_file_id("\n  x", 3));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(Position::new(3, 2, 3, 3), Position::new(3, 2, 4, 4), None, Box::new("x".to_string()))))
+    }
+
+
+
+    // Testing input sources //////////////////////////////////////////////////
+
+    #[test]
+    fn input_owned_and_borrowed() {
+
+        let text = format!("({} {})", "ä", 1);
+        let mut borrowed = Box::new(LexicalAnalyzer::new(text.as_str()));
+        let owned = Box::new(LexicalAnalyzer::new(text.clone()));
+
+        assert_eq!(borrowed.get_symbol(), Ok(Symbols::LeftParen(p(0), p(1))));
+        assert_eq!(borrowed.get_symbol(), Ok(Symbols::LiteralName(p(1), Position::new(0, 1, 3, 3), None, Box::new("ä".to_string()))));
+        assert_eq!(owned.source(), borrowed.source())
+    }
+
+    #[test]
+    fn input_from_reader() {
+
+        let mut lexer = LexicalAnalyzer::from_reader("[a]".as_bytes(), 2).unwrap();
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftBracket(Position::new(2, 1, 1, 0), Position::new(2, 1, 2, 1))));
+        assert_eq!(LexicalAnalyzer::from_reader(&[0x28, 0xff][..], 0).err().map(|x| x.kind()), Some(std::io::ErrorKind::InvalidData))
+    }
+
+    #[test]
+    fn input_from_file() {
+
+        let path = std::env::temp_dir().join(format!("lexical_analyzer_{}.clj", std::process::id()));
+        std::fs::write(&path, "(ns user)").unwrap();
+        let lexer = LexicalAnalyzer::from_file(&path, 1);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(lexer.unwrap().get_symbol(), Ok(Symbols::LeftParen(Position::new(1, 1, 1, 0), Position::new(1, 1, 2, 1))));
+        assert!(LexicalAnalyzer::from_file(&path, 1).is_err())
+    }
+
+
+
     // Testing whitespace like comments, newlines etc /////////////////////////
 
+    #[test]
+    fn commas_and_unicode_whitespace() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("{:a 1, :b 2},\u{000C}x\u{2003}y\u{00A0}z"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftCurly(p(0), p(1))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralKeyword(p(1), p(3), None, Box::new("a".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(4), p(5), 1)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralKeyword(p(7), p(9), None, Box::new("b".to_string()))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralInteger(p(10), p(11), 2)));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::RightCurly(p(11), p(12))));
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LiteralName(p(14), p(15), None, Box::new("x".to_string()))));
+        assert_eq!(lexer.get_symbol().map(|x| x.to_string()), Ok("y\u{00A0}z".to_string()));
+        assert_eq!(lexer.get_symbol().map(|x| x.to_string()), Ok("".to_string()))
+    }
+
+    #[test]
+    fn shebang_comment() {
+
+        let mut lexer = Box::new(LexicalAnalyzer::new("#!/usr/bin/env clj\n(a)"));
+
+        assert_eq!(lexer.get_symbol(), Ok(Symbols::LeftParen(Position::new(0, 2, 1, 19), Position::new(0, 2, 2, 20))))
+    }
+
     #[test]
     fn single_semicolon_comment() {
 
@@ -975,7 +2222,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::EOF => assert!(true),
+                    Symbols::EOF(start, end) if start == p(48) && end == p(48) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -991,7 +2238,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::EOF => assert!(true),
+                    Symbols::EOF(start, end) if start == p(49) && end == p(49) => assert!(true),
                     _ => assert!(false)
                 }
             },
@@ -1007,7 +2254,7 @@ mod tests {
         match lexer.get_symbol() { 
             Ok(x) => {
                 match x {
-                    Symbols::EOF => assert!(true),
+                    Symbols::EOF(start, end) if start == p(58) && end == p(58) => assert!(true),
                     _ => assert!(false)
                 }
             },
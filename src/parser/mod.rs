@@ -1,2 +1,8 @@
-mod lexical_analyzer;
-mod expression_parser;
\ No newline at end of file
+pub mod source_position;
+pub mod syntax_error;
+pub mod lexical_analyzer;
+pub mod expression_parser;
+pub mod incremental_reader;
+pub mod syntax_tree;
+pub mod node_visitor;
+pub mod node_zipper;
\ No newline at end of file
@@ -0,0 +1,353 @@
+use super::expression_parser::Node;
+
+/* Read only walk over a tree of nodes. Every method visits the subforms by default,
+ * so an implementation only overrides the nodes it cares about and calls walk to keep going.
+ * Metadata is not a subform, a visitor that wants it reads Node::metadata.
+ */
+pub trait Visitor {
+    fn visit_node(&mut self, node: &Node) {
+        match node {
+            Node::Unit(_, _) => self.visit_unit(node),
+            Node::List(_, _, _, _) => self.visit_list(node),
+            Node::Vec(_, _, _, _) => self.visit_vec(node),
+            Node::Map(_, _, _, _) => self.visit_map(node),
+            Node::Set(_, _, _, _) => self.visit_set(node),
+            Node::KeyValue(_, _) => self.visit_key_value(node),
+            Node::Conditional(_, _) => self.visit_conditional(node),
+            Node::Tagged(_, _) => self.visit_tagged(node),
+            Node::Error(_) => self.visit_error(node),
+            _ => self.visit_literal(node)
+        }
+    }
+
+    fn visit_unit(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    fn visit_list(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    fn visit_vec(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    fn visit_map(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    fn visit_set(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    fn visit_key_value(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    fn visit_conditional(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    fn visit_tagged(&mut self, node: &Node) {
+        walk(self, node)
+    }
+
+    /* Nil, booleans, numbers, strings, characters, keywords, symbols and regular expressions */
+    fn visit_literal(&mut self, _node: &Node) {}
+
+    fn visit_error(&mut self, _node: &Node) {}
+}
+
+/* Same as Visitor, but nodes can be changed in place */
+pub trait VisitorMut {
+    fn visit_node_mut(&mut self, node: &mut Node) {
+        match node {
+            Node::Unit(_, _) => self.visit_unit_mut(node),
+            Node::List(_, _, _, _) => self.visit_list_mut(node),
+            Node::Vec(_, _, _, _) => self.visit_vec_mut(node),
+            Node::Map(_, _, _, _) => self.visit_map_mut(node),
+            Node::Set(_, _, _, _) => self.visit_set_mut(node),
+            Node::KeyValue(_, _) => self.visit_key_value_mut(node),
+            Node::Conditional(_, _) => self.visit_conditional_mut(node),
+            Node::Tagged(_, _) => self.visit_tagged_mut(node),
+            Node::Error(_) => self.visit_error_mut(node),
+            _ => self.visit_literal_mut(node)
+        }
+    }
+
+    fn visit_unit_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_list_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_vec_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_map_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_set_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_key_value_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_conditional_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_tagged_mut(&mut self, node: &mut Node) {
+        walk_mut(self, node)
+    }
+
+    fn visit_literal_mut(&mut self, _node: &mut Node) {}
+
+    fn visit_error_mut(&mut self, _node: &mut Node) {}
+}
+
+/* Rebuilds a tree bottom up, taking each node by value and giving back its replacement.
+ * By default subforms are folded first and everything else is returned unchanged.
+ */
+pub trait Fold {
+    fn fold_node(&mut self, node: Node) -> Node {
+        match node {
+            Node::Unit(_, _) => self.fold_unit(node),
+            Node::List(_, _, _, _) => self.fold_list(node),
+            Node::Vec(_, _, _, _) => self.fold_vec(node),
+            Node::Map(_, _, _, _) => self.fold_map(node),
+            Node::Set(_, _, _, _) => self.fold_set(node),
+            Node::KeyValue(_, _) => self.fold_key_value(node),
+            Node::Conditional(_, _) => self.fold_conditional(node),
+            Node::Tagged(_, _) => self.fold_tagged(node),
+            Node::Error(_) => self.fold_error(node),
+            _ => self.fold_literal(node)
+        }
+    }
+
+    fn fold_unit(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_list(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_vec(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_map(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_set(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_key_value(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_conditional(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_tagged(&mut self, node: Node) -> Node {
+        fold_children(self, node)
+    }
+
+    fn fold_literal(&mut self, node: Node) -> Node {
+        node
+    }
+
+    fn fold_error(&mut self, node: Node) -> Node {
+        node
+    }
+}
+
+/* Visits the direct subforms of the node */
+pub fn walk<V: Visitor + ?Sized>(visitor: &mut V, node: &Node) {
+    for child in node.children() {
+        visitor.visit_node(child)
+    }
+}
+
+pub fn walk_mut<V: VisitorMut + ?Sized>(visitor: &mut V, node: &mut Node) {
+    for child in node.children_mut() {
+        visitor.visit_node_mut(child)
+    }
+}
+
+/* Folds the direct subforms of the node and puts the results back in their place, metadata is kept as it is */
+pub fn fold_children<F: Fold + ?Sized>(folder: &mut F, node: Node) -> Node {
+    let mut fold = |x: Box<Node>| Box::new(folder.fold_node(*x));
+    match node {
+        Node::Unit(span, nodes) => Node::Unit(span, nodes.into_iter().map(|x| folder.fold_node(x)).collect()),
+        Node::List(start, nodes, end, metadata) => Node::List(start, nodes.into_iter().map(|x| folder.fold_node(x)).collect(), end, metadata),
+        Node::Vec(start, nodes, end, metadata) => Node::Vec(start, nodes.into_iter().map(|x| folder.fold_node(x)).collect(), end, metadata),
+        Node::Map(start, nodes, end, metadata) => Node::Map(start, nodes.into_iter().map(|x| folder.fold_node(x)).collect(), end, metadata),
+        Node::Set(start, nodes, end, metadata) => Node::Set(start, nodes.into_iter().map(|x| folder.fold_node(x)).collect(), end, metadata),
+        Node::KeyValue(key, value) => Node::KeyValue(fold(key), fold(value)),
+        Node::Conditional(start, form) => Node::Conditional(start, fold(form)),
+        Node::Tagged(tag, form) => Node::Tagged(tag, fold(form)),
+        x => x
+    }
+}
+
+/* Depth first iterator giving each node before its subforms */
+pub struct PreOrder<'a> {
+    stack: Vec<&'a Node>
+}
+
+/* Depth first iterator giving each node after its subforms */
+pub struct PostOrder<'a> {
+    stack: Vec<(&'a Node, bool)>
+}
+
+pub fn pre_order(node: &Node) -> PreOrder<'_> {
+    PreOrder { stack: vec![node] }
+}
+
+pub fn post_order(node: &Node) -> PostOrder<'_> {
+    PostOrder { stack: vec![(node, false)] }
+}
+
+impl<'a> Iterator for PreOrder<'a> {
+    type Item = &'a Node;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children().into_iter().rev());
+        Some(node)
+    }
+}
+
+impl<'a> Iterator for PostOrder<'a> {
+    type Item = &'a Node;
+
+    /* A node is pushed back marked as expanded below its subforms, and given once they are done */
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.stack.pop()? {
+                (node, true) => return Some(node),
+                (node, false) => {
+                    self.stack.push((node, true));
+                    self.stack.extend(node.children().into_iter().rev().map(|x| (x, false)))
+                }
+            }
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::expression_parser::{ ExpressionParser, ExpressionParserMethods, Node };
+    use crate::parser::lexical_analyzer::{ LexicalAnalyzer, LexicalAnalyzerMethods };
+
+    use super::{ Fold, Visitor, VisitorMut, fold_children, post_order, pre_order, walk };
+
+    fn parse(source: &str) -> Node {
+        ExpressionParser::new(LexicalAnalyzer::new(source)).parse_start_unit().unwrap()
+    }
+
+    #[test]
+    fn visitor_collects_symbols() {
+        struct Names(Vec<String>, usize);
+
+        impl Visitor for Names {
+            fn visit_literal(&mut self, node: &Node) {
+                if let Node::Symbol(_, _, name, _) = node {
+                    self.0.push(name.to_string())
+                }
+            }
+
+            /* Maps are counted but not entered */
+            fn visit_map(&mut self, _node: &Node) {
+                self.1 += 1
+            }
+
+            fn visit_vec(&mut self, node: &Node) {
+                self.0.push("[".to_string());
+                walk(self, node)
+            }
+        }
+
+        let mut visitor = Names(vec![], 0);
+        visitor.visit_node(&parse("(defn f [a b] ^:private {x y} #{c} #inst \"2024-01-01\")"));
+
+        assert_eq!(visitor.0, vec!["defn", "f", "[", "a", "b", "c"]);
+        assert_eq!(visitor.1, 1)
+    }
+
+    #[test]
+    fn visitor_mut_renames_symbols() {
+        struct Rename;
+
+        impl VisitorMut for Rename {
+            fn visit_literal_mut(&mut self, node: &mut Node) {
+                if let Node::Symbol(_, None, name, _) = node {
+                    if name.as_str() == "x" {
+                        **name = "y".to_string()
+                    }
+                }
+            }
+        }
+
+        let mut node = parse("(let [x 1] {:a x} (inc x))");
+        Rename.visit_node_mut(&mut node);
+
+        assert_eq!(node.to_string(), "(let [y 1] {:a y} (inc y))")
+    }
+
+    #[test]
+    fn fold_replaces_nodes() {
+        struct Double;
+
+        impl Fold for Double {
+            fn fold_literal(&mut self, node: Node) -> Node {
+                match node {
+                    Node::Long(span, value) => Node::Long(span, value * 2),
+                    x => x
+                }
+            }
+
+            /* Vectors are folded first, then turned into lists */
+            fn fold_vec(&mut self, node: Node) -> Node {
+                match fold_children(self, node) {
+                    Node::Vec(start, nodes, end, metadata) => Node::List(start, nodes, end, metadata),
+                    x => x
+                }
+            }
+        }
+
+        let node = Double.fold_node(parse("(+ 1 [2 {:a 3}] ^{:b 4} #{5})"));
+
+        assert_eq!(node.to_string(), "(+ 2 (4 {:a 6}) ^{:b 4} #{10})")
+    }
+
+    #[test]
+    fn pre_and_post_order() {
+        let node = parse("(a [b c] {d e})");
+        let order = |nodes: Vec<&Node>| nodes.iter().map(|x| x.to_string()).collect::<Vec<String>>();
+
+        assert_eq!(order(pre_order(&node).collect()), vec![
+            "(a [b c] {d e})", "(a [b c] {d e})", "a", "[b c]", "b", "c", "{d e}", "d e", "d", "e"
+        ]);
+        assert_eq!(order(post_order(&node).collect()), vec![
+            "a", "b", "c", "[b c]", "d", "e", "d e", "{d e}", "(a [b c] {d e})", "(a [b c] {d e})"
+        ])
+    }
+}
@@ -0,0 +1,480 @@
+use super::expression_parser::Node;
+
+pub trait NodeZipperMethods {
+    fn new(root: Node) -> Self;
+    fn node(&self) -> &Node;
+    fn is_end(&self) -> bool;
+    fn down(&mut self) -> bool;
+    fn up(&mut self) -> bool;
+    fn left(&mut self) -> bool;
+    fn right(&mut self) -> bool;
+    fn rightmost(&mut self);
+    fn next(&mut self) -> bool;
+    fn root(self) -> Node;
+    fn replace(&mut self, node: Node) -> bool;
+    fn edit(&mut self, f: impl FnOnce(&Node) -> Node) -> bool;
+    fn insert_left(&mut self, node: Node) -> bool;
+    fn insert_right(&mut self, node: Node) -> bool;
+    fn insert_child(&mut self, node: Node) -> bool;
+    fn append_child(&mut self, node: Node) -> bool;
+    fn remove(&mut self) -> bool;
+}
+
+/* The way down to the focus, the parent with its subforms taken out and the siblings on each side.
+ * Right siblings are kept reversed, so the nearest one is popped from the end.
+ */
+struct Crumb {
+    parent: Node,
+    left: Vec<Node>,
+    right: Vec<Node>
+}
+
+/* Moves around a tree of nodes and edits it without mutating it, like clojure.zip.
+ * Moves return false and leave the focus where it was when there is nowhere to go, edits when the node does not fit.
+ * Edited parents keep their delimiters, so spans of changed collections are no longer exact.
+ */
+pub struct NodeZipper {
+    focus: Node,
+    path: Vec<Crumb>,
+    end: bool
+}
+
+impl NodeZipperMethods for NodeZipper {
+
+    fn new(root: Node) -> Self {
+        NodeZipper {
+            focus: root,
+            path: Vec::new(),
+            end: false
+        }
+    }
+
+    fn node(&self) -> &Node {
+        &self.focus
+    }
+
+    /* True after next has walked past the last node */
+    fn is_end(&self) -> bool {
+        self.end
+    }
+
+    /* The subforms are moved out of the focus, up puts them back */
+    fn down(&mut self) -> bool {
+        if self.focus.children().is_empty() {
+            return false
+        }
+        let mut right = take_children(&mut self.focus);
+        right.reverse();
+        let first = right.pop().unwrap();
+        let parent = std::mem::replace(&mut self.focus, first);
+        self.path.push(Crumb { parent, left: Vec::new(), right });
+        true
+    }
+
+    fn up(&mut self) -> bool {
+        match self.path.pop() {
+            Some(Crumb { mut parent, mut left, right }) => {
+                let focus = std::mem::replace(&mut self.focus, placeholder(&parent));
+                left.push(focus);
+                left.extend(right.into_iter().rev());
+                put_children(&mut parent, left);
+                self.focus = parent;
+                true
+            },
+            None => false
+        }
+    }
+
+    fn left(&mut self) -> bool {
+        match self.path.last_mut() {
+            Some(crumb) => match crumb.left.pop() {
+                Some(node) => {
+                    crumb.right.push(std::mem::replace(&mut self.focus, node));
+                    true
+                },
+                None => false
+            },
+            None => false
+        }
+    }
+
+    fn right(&mut self) -> bool {
+        match self.path.last_mut() {
+            Some(crumb) => match crumb.right.pop() {
+                Some(node) => {
+                    crumb.left.push(std::mem::replace(&mut self.focus, node));
+                    true
+                },
+                None => false
+            },
+            None => false
+        }
+    }
+
+    fn rightmost(&mut self) {
+        while self.right() {}
+    }
+
+    /* Depth first walk, the subforms of a node come right after it */
+    fn next(&mut self) -> bool {
+        if self.end {
+            return false
+        }
+        if self.down() || self.right() {
+            return true
+        }
+        while self.up() {
+            if self.right() {
+                return true
+            }
+        }
+        self.end = true;
+        false
+    }
+
+    /* Goes all the way up and gives back the edited tree */
+    fn root(mut self) -> Node {
+        while self.up() {}
+        self.focus
+    }
+
+    /* Same rules as inserting, the focus is left as it was when the node does not fit in its place */
+    fn replace(&mut self, node: Node) -> bool {
+        if !self.fits(&node) {
+            return false
+        }
+        self.focus = node;
+        true
+    }
+
+    fn edit(&mut self, f: impl FnOnce(&Node) -> Node) -> bool {
+        let node = f(&self.focus);
+        self.replace(node)
+    }
+
+    /* Siblings can only be added inside collections, not next to a key, value or the form of a tag.
+     * A map only takes key value pairs with a new key, and a set only takes new elements.
+     */
+    fn insert_left(&mut self, node: Node) -> bool {
+        match self.path.last_mut() {
+            Some(crumb) if accepts(&crumb.parent, crumb.left.iter().chain(Some(&self.focus)).chain(crumb.right.iter()), &node) => {
+                crumb.left.push(node);
+                true
+            },
+            _ => false
+        }
+    }
+
+    fn insert_right(&mut self, node: Node) -> bool {
+        match self.path.last_mut() {
+            Some(crumb) if accepts(&crumb.parent, crumb.left.iter().chain(Some(&self.focus)).chain(crumb.right.iter()), &node) => {
+                crumb.right.push(node);
+                true
+            },
+            _ => false
+        }
+    }
+
+    /* Adds the node as the first subform of the collection in focus */
+    fn insert_child(&mut self, node: Node) -> bool {
+        if !accepts(&self.focus, self.focus.children().into_iter(), &node) {
+            return false
+        }
+        let mut children = take_children(&mut self.focus);
+        children.insert(0, node);
+        put_children(&mut self.focus, children);
+        true
+    }
+
+    fn append_child(&mut self, node: Node) -> bool {
+        if !accepts(&self.focus, self.focus.children().into_iter(), &node) {
+            return false
+        }
+        let mut children = take_children(&mut self.focus);
+        children.push(node);
+        put_children(&mut self.focus, children);
+        true
+    }
+
+    /* Removes the focus and moves to the node before it in a depth first walk, so next continues after it */
+    fn remove(&mut self) -> bool {
+        match self.path.last_mut() {
+            Some(crumb) if crumb.parent.is_sequence() => match crumb.left.pop() {
+                Some(node) => {
+                    self.focus = node;
+                    while self.down() {
+                        self.rightmost()
+                    }
+                    true
+                },
+                None => {
+                    let Crumb { mut parent, right, .. } = self.path.pop().unwrap();
+                    put_children(&mut parent, right.into_iter().rev().collect());
+                    self.focus = parent;
+                    true
+                }
+            },
+            _ => false
+        }
+    }
+}
+
+impl NodeZipper {
+    /* Whether the node can take the place of the focus. A new map key must differ from the keys of the other entries */
+    fn fits(&self, node: &Node) -> bool {
+        let crumb = match self.path.last() {
+            Some(crumb) => crumb,
+            None => return true
+        };
+        let siblings = crumb.left.iter().chain(crumb.right.iter());
+        match &crumb.parent {
+            Node::KeyValue(_, _) if crumb.left.is_empty() && self.path.len() > 1 => {
+                let entries = &self.path[self.path.len() - 2];
+                !is_pair(node) && is_new(node, entries.left.iter().chain(entries.right.iter()))
+            },
+            Node::KeyValue(_, _) | Node::Conditional(_, _) | Node::Tagged(_, _) => !is_pair(node),
+            parent => accepts(parent, siblings, node)
+        }
+    }
+}
+
+/* Cheap node left behind where a subform was moved out */
+fn placeholder(node: &Node) -> Node {
+    Node::Unit(node.span(), vec![])
+}
+
+/* Moves the subforms out of the node in source order, the node keeps its delimiters and metadata */
+fn take_children(node: &mut Node) -> Vec<Node> {
+    let take = |x: &mut Box<Node>| {
+        let empty = placeholder(x);
+        std::mem::replace(x.as_mut(), empty)
+    };
+    match node {
+        Node::Unit(_, nodes) |
+        Node::List(_, nodes, _, _) |
+        Node::Vec(_, nodes, _, _) |
+        Node::Map(_, nodes, _, _) |
+        Node::Set(_, nodes, _, _) => std::mem::take(nodes),
+        Node::KeyValue(key, value) => vec![take(key), take(value)],
+        Node::Conditional(_, form) |
+        Node::Tagged(_, form) => vec![take(form)],
+        _ => vec![]
+    }
+}
+
+/* Puts subforms taken by take_children back, a key, value or form not given stays as it is */
+fn put_children(node: &mut Node, children: Vec<Node>) {
+    match node {
+        Node::Unit(_, nodes) |
+        Node::List(_, nodes, _, _) |
+        Node::Vec(_, nodes, _, _) |
+        Node::Map(_, nodes, _, _) |
+        Node::Set(_, nodes, _, _) => *nodes = children,
+        x => {
+            for (slot, child) in x.children_mut().into_iter().zip(children) {
+                *slot = child
+            }
+        }
+    }
+}
+
+/* Whether the node can be added to the collection holding the given subforms.
+ * Key value pairs only go in maps, and map keys and set elements are compared by value like the reader does.
+ */
+fn accepts<'n>(parent: &Node, children: impl Iterator<Item = &'n Node>, node: &Node) -> bool {
+    match parent {
+        Node::Map(_, _, _, _) => is_pair(node) && is_new(node, children),
+        Node::Set(_, _, _, _) => !is_pair(node) && is_new(node, children),
+        x => x.is_sequence() && !is_pair(node)
+    }
+}
+
+fn is_pair(node: &Node) -> bool {
+    matches!(node, Node::KeyValue(_, _))
+}
+
+/* The key of a map entry, or the value of any other form, is not among the given subforms */
+fn is_new<'n>(node: &Node, mut children: impl Iterator<Item = &'n Node>) -> bool {
+    let key = |x: &Node| match x {
+        Node::KeyValue(key, _) => key.value_key(),
+        x => x.value_key()
+    };
+    match key(node) {
+        Some(value) => !children.any(|x| key(x).as_ref() == Some(&value)),
+        None => true
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::expression_parser::{ ExpressionParser, ExpressionParserMethods, Node };
+    use crate::parser::lexical_analyzer::{ LexicalAnalyzer, LexicalAnalyzerMethods, Symbols };
+    use crate::parser::source_position::{ Position, Span };
+
+    use super::{ NodeZipper, NodeZipperMethods };
+
+    fn parse(source: &str) -> Node {
+        ExpressionParser::new(LexicalAnalyzer::new(source)).parse_start_unit().unwrap()
+    }
+
+    fn symbol(name: &str) -> Node {
+        let start = Position::start_of_file(0);
+        Node::Symbol(Span::new(start, start), None, Box::new(name.to_string()), None)
+    }
+
+    #[test]
+    fn navigation() {
+        let mut zipper = NodeZipper::new(parse("(a [b c] d)"));
+
+        assert!(!zipper.up());
+        assert!(!zipper.right());
+        assert!(zipper.down() && zipper.down());
+        assert_eq!(zipper.node().to_string(), "a");
+        assert!(!zipper.left());
+        assert!(zipper.right());
+        assert!(zipper.down());
+        assert_eq!(zipper.node().to_string(), "b");
+        assert!(!zipper.down());
+        assert!(zipper.up() && zipper.right());
+        assert_eq!(zipper.node().to_string(), "d");
+        assert!(!zipper.right());
+        assert!(zipper.left() && zipper.up());
+        assert_eq!(zipper.node().to_string(), "(a [b c] d)");
+        assert_eq!(zipper.root(), parse("(a [b c] d)"))
+    }
+
+    #[test]
+    fn edit_and_insert() {
+        let mut zipper = NodeZipper::new(parse("(a [b] {:k v})"));
+        zipper.down();
+        zipper.down();
+        assert!(zipper.replace(symbol("f")));
+        assert!(zipper.insert_left(symbol("g")) && zipper.insert_right(symbol("h")));
+        zipper.right();
+        zipper.right();
+        assert!(zipper.insert_child(symbol("x")) && zipper.append_child(symbol("y")));
+        zipper.right();
+        zipper.down();
+        zipper.down();
+        assert!(!zipper.insert_left(symbol("z")));
+        zipper.right();
+        assert!(zipper.edit(|x| {
+            let span = x.span();
+            Node::Vec(Symbols::LeftBracket(span.start, span.start), vec![x.clone()], Symbols::RightBracket(span.end, span.end), None)
+        }));
+
+        assert_eq!(zipper.root().to_string(), "(g f h [x b y] {:k [v]})")
+    }
+
+    #[test]
+    fn insert_into_map_and_set() {
+        let pair = |key: &str, value: &str| Node::KeyValue(Box::new(symbol(key)), Box::new(symbol(value)));
+        let mut zipper = NodeZipper::new(parse("{a 1} #{b}"));
+        zipper.down();
+
+        assert!(!zipper.append_child(symbol("c")));
+        assert!(!zipper.insert_child(pair("a", "2")));
+        assert!(zipper.append_child(pair("c", "3")));
+        zipper.down();
+        assert!(!zipper.insert_right(symbol("d")));
+        assert!(!zipper.insert_left(pair("c", "4")));
+        assert!(zipper.insert_left(pair("e", "5")));
+        zipper.up();
+        assert!(!zipper.insert_right(pair("f", "6")));
+        zipper.right();
+        assert!(!zipper.insert_child(pair("g", "7")));
+        assert!(!zipper.append_child(symbol("b")));
+        assert!(zipper.append_child(symbol("h")));
+        zipper.down();
+        assert!(!zipper.insert_right(symbol("h")));
+
+        assert_eq!(zipper.root().to_string(), "{e 5, a 1, c 3}\n#{b h}")
+    }
+
+    #[test]
+    fn replace_in_map_and_set() {
+        let pair = |key: &str, value: &str| Node::KeyValue(Box::new(symbol(key)), Box::new(symbol(value)));
+        let mut zipper = NodeZipper::new(parse("{a 1, b 2} #{c d} [e]"));
+        zipper.down();
+        zipper.down();
+        zipper.right();
+
+        assert!(!zipper.replace(symbol("f")));
+        assert!(!zipper.replace(pair("a", "3")));
+        assert!(zipper.edit(|x| match x {
+            Node::KeyValue(_, value) => Node::KeyValue(Box::new(symbol("b")), value.clone()),
+            x => x.clone()
+        }));
+        zipper.down();
+        assert!(!zipper.replace(symbol("a")));
+        assert!(!zipper.replace(pair("g", "4")));
+        assert!(zipper.replace(symbol("g")));
+        zipper.right();
+        assert!(zipper.replace(symbol("a")));
+        zipper.up();
+        zipper.up();
+        zipper.right();
+        zipper.down();
+        assert!(!zipper.replace(symbol("d")));
+        assert!(!zipper.replace(pair("c", "5")));
+        assert!(zipper.replace(symbol("h")));
+        zipper.up();
+        zipper.right();
+        zipper.down();
+        assert!(!zipper.replace(pair("e", "6")));
+
+        assert_eq!(zipper.root().to_string(), "{a 1, g a}\n#{h d}\n[e]")
+    }
+
+    #[test]
+    fn walk_keeps_tree() {
+        let source = "(a [b] #inst \"2024\" ^:m {:k 'v})";
+        let mut zipper = NodeZipper::new(parse(source));
+        let mut count = 0;
+        while zipper.next() {
+            count += 1
+        }
+
+        assert_eq!(count, 12);
+        assert_eq!(zipper.root(), parse(source))
+    }
+
+    #[test]
+    fn next_walks_depth_first() {
+        let mut zipper = NodeZipper::new(parse("(a [b c] ^:m d)"));
+        let mut seen = vec![];
+        while zipper.next() {
+            seen.push(zipper.node().to_string())
+        }
+
+        assert!(zipper.is_end());
+        assert_eq!(seen, vec!["(a [b c] ^{:m true} d)", "a", "[b c]", "b", "c", "^{:m true} d"]);
+        assert_eq!(zipper.node().to_string(), "(a [b c] ^{:m true} d)")
+    }
+
+    #[test]
+    fn remove_while_walking() {
+        let mut zipper = NodeZipper::new(parse("(a 1 [2 b] (3))"));
+        while zipper.next() {
+            if let Node::Long(_, _) = zipper.node() {
+                assert!(zipper.remove())
+            }
+        }
+
+        assert_eq!(zipper.root().to_string(), "(a [b] ())");
+
+        let mut zipper = NodeZipper::new(parse("{:a 1}"));
+        zipper.down();
+        zipper.down();
+        zipper.down();
+        assert!(!zipper.remove());
+        assert!(zipper.up() && zipper.remove());
+        assert_eq!(zipper.root().to_string(), "{}")
+    }
+}
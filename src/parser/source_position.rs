@@ -0,0 +1,109 @@
+/* A location in a source file, line and column start at 1 and offset is counted in bytes from the start */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct Position {
+    pub file_id: u32,
+    pub line: u32,
+    pub column: u32,
+    pub offset: u32
+}
+
+/* The part of a source file covered by a token or node, end is the position after the last character */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position
+}
+
+/* Maps the file id stored in every position back to the file name */
+#[derive(Clone, PartialEq, Debug, Default)]
+#[allow(clippy::vec_box)]
+pub struct SourceMap {
+    files: Vec<Box<String>>
+}
+
+impl Position {
+    pub fn new(file_id: u32, line: u32, column: u32, offset: u32) -> Self {
+        Position { file_id, line, column, offset }
+    }
+
+    pub fn start_of_file(file_id: u32) -> Self {
+        Position::new(file_id, 1, 1, 0)
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position::start_of_file(0)
+    }
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    /* Smallest span covering both spans */
+    pub fn merge(&self, other: &Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap { files: Vec::new() }
+    }
+
+    pub fn add_file(&mut self, name: &str) -> u32 {
+        self.files.push(Box::new(name.to_string()));
+        (self.files.len() - 1) as u32
+    }
+
+    pub fn file_name(&self, file_id: u32) -> Option<&str> {
+        self.files.get(file_id as usize).map(|x| x.as_str())
+    }
+
+    /* Formats a position as 'file.clj:12:7', unknown files only show line and column */
+    pub fn location(&self, position: &Position) -> String {
+        match self.file_name(position.file_id) {
+            Some(name) => format!("{}:{}", name, position),
+            None => position.to_string()
+        }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Position, SourceMap, Span};
+
+    #[test]
+    fn location_with_file_name() {
+        let mut files = SourceMap::new();
+        let core = files.add_file("core.clj");
+        let user = files.add_file("src/user.clj");
+
+        assert_eq!(files.location(&Position::new(core, 1, 1, 0)), "core.clj:1:1");
+        assert_eq!(files.location(&Position::new(user, 12, 7, 230)), "src/user.clj:12:7");
+        assert_eq!(files.location(&Position::new(5, 3, 2, 10)), "3:2")
+    }
+
+    #[test]
+    fn merge_spans() {
+        let first = Span::new(Position::new(0, 1, 1, 0), Position::new(0, 1, 4, 3));
+        let second = Span::new(Position::new(0, 2, 1, 10), Position::new(0, 2, 5, 14));
+
+        assert_eq!(first.merge(&second), Span::new(Position::new(0, 1, 1, 0), Position::new(0, 2, 5, 14)));
+        assert_eq!(second.merge(&first), first.merge(&second))
+    }
+}
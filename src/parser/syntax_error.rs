@@ -0,0 +1,271 @@
+use super::source_position::{ Position, SourceMap, Span };
+
+/* Errors found while reading source text, every error carries the span of the offending text */
+#[derive(Clone, PartialEq, Debug)]
+pub enum SyntaxError {
+    IllegalCharacter(Span, char),
+    InvalidToken(Span, Box<String>),
+    InvalidNumber(Span, Box<String>),
+    DivideByZero(Span, Box<String>),
+    RadixOutOfRange(Span, Box<String>),
+    UnterminatedString(Span),
+    UnterminatedRegex(Span),
+    UnsupportedEscape(Span, char),
+    InvalidUnicodeEscape(Span, Box<String>),
+    InvalidOctalEscape(Span, Box<String>),
+    InvalidOctalLength(Span, Box<String>),
+    UnterminatedCharacter(Span),
+    UnsupportedCharacter(Span, Box<String>),
+    InvalidUnicodeCharacter(Span, Box<String>),
+    InvalidCharacterConstant(Span, Box<String>),
+    MismatchedDelimiter(Span, char, char, Span),
+    UnmatchedDelimiter(Span, char),
+    UnknownSymbolicValue(Span, Box<String>),
+
+    UnexpectedToken(Span, Box<String>),
+    UnexpectedEndOfFile(Span),
+    UnexpectedClosingDelimiter(Span),
+    MissingClosingDelimiter(Span, char, Span, Option<Position>),
+    OddNumberOfMapForms(Span),
+    DuplicateKey(Span, Box<String>),
+    MissingDispatchForm(Span),
+    UnquoteSplicingNotInList(Span),
+    NestedAnonymousFn(Span),
+    InvalidArgLiteral(Span, Box<String>),
+    InvalidMetadata(Span),
+    InvalidMetadataTarget(Span),
+    ReaderConditionalNotList(Span),
+    ReaderConditionalOddForms(Span),
+    FeatureNotKeyword(Span),
+    SplicingNotInCollection(Span),
+    SplicedFormNotSequential(Span),
+    UnknownTag(Span, Box<String>),
+    InvalidTaggedLiteral(Span, Box<String>),
+    InvalidDataReaders(Span)
+}
+
+impl SyntaxError {
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxError::IllegalCharacter(span, _) |
+            SyntaxError::InvalidToken(span, _) |
+            SyntaxError::InvalidNumber(span, _) |
+            SyntaxError::DivideByZero(span, _) |
+            SyntaxError::RadixOutOfRange(span, _) |
+            SyntaxError::UnterminatedString(span) |
+            SyntaxError::UnterminatedRegex(span) |
+            SyntaxError::UnsupportedEscape(span, _) |
+            SyntaxError::InvalidUnicodeEscape(span, _) |
+            SyntaxError::InvalidOctalEscape(span, _) |
+            SyntaxError::InvalidOctalLength(span, _) |
+            SyntaxError::UnterminatedCharacter(span) |
+            SyntaxError::UnsupportedCharacter(span, _) |
+            SyntaxError::InvalidUnicodeCharacter(span, _) |
+            SyntaxError::InvalidCharacterConstant(span, _) |
+            SyntaxError::MismatchedDelimiter(span, _, _, _) |
+            SyntaxError::UnmatchedDelimiter(span, _) |
+            SyntaxError::UnknownSymbolicValue(span, _) |
+            SyntaxError::UnexpectedToken(span, _) |
+            SyntaxError::UnexpectedEndOfFile(span) |
+            SyntaxError::UnexpectedClosingDelimiter(span) |
+            SyntaxError::MissingClosingDelimiter(span, _, _, _) |
+            SyntaxError::OddNumberOfMapForms(span) |
+            SyntaxError::DuplicateKey(span, _) |
+            SyntaxError::MissingDispatchForm(span) |
+            SyntaxError::UnquoteSplicingNotInList(span) |
+            SyntaxError::NestedAnonymousFn(span) |
+            SyntaxError::InvalidArgLiteral(span, _) |
+            SyntaxError::InvalidMetadata(span) |
+            SyntaxError::InvalidMetadataTarget(span) |
+            SyntaxError::ReaderConditionalNotList(span) |
+            SyntaxError::ReaderConditionalOddForms(span) |
+            SyntaxError::FeatureNotKeyword(span) |
+            SyntaxError::SplicingNotInCollection(span) |
+            SyntaxError::SplicedFormNotSequential(span) |
+            SyntaxError::UnknownTag(span, _) |
+            SyntaxError::InvalidTaggedLiteral(span, _) |
+            SyntaxError::InvalidDataReaders(span) => *span
+        }
+    }
+
+    /* Formats the error together with the offending source line and a caret underline, like:
+     *
+     *   error: Invalid number: 12abc
+     *    --> core.clj:3:5
+     *     |
+     *   3 | (+ 12abc 1)
+     *     |    ^^^^^
+     *
+     * Delimiter errors add notes in the same format pointing at where the delimiter was opened.
+     */
+    pub fn render(&self, source: &str, files: &SourceMap) -> String {
+        let mut text = format!("error: {}\n{}", self, snippet(self.span(), source, files));
+        for (label, span) in self.notes() {
+            text.push_str(&format!("\n{}\n{}", label, snippet(span, source, files)))
+        }
+        text
+    }
+
+    /* Related locations shown below the error */
+    fn notes(&self) -> Vec<(String, Span)> {
+        match self {
+            SyntaxError::MismatchedDelimiter(_, _, expected, opened) => {
+                vec![(format!("note: '{}' opened here, expected '{}' to close it", opening_of(*expected), expected), *opened)]
+            },
+            SyntaxError::MissingClosingDelimiter(_, expected, opened, suggestion) => {
+                let mut notes = vec![(format!("note: unclosed '{}' opened here", opening_of(*expected)), *opened)];
+                if let Some(position) = suggestion {
+                    notes.push((format!("help: '{}' is probably missing here, judging by the indentation", expected), Span::new(*position, *position)))
+                }
+                notes
+            },
+            _ => vec![]
+        }
+    }
+}
+
+/* The location, source line and caret underline of a span. Only the location is given when
+ * the span does not fit the source, like spans of a stream whose start has been dropped.
+ */
+pub(crate) fn snippet(span: Span, source: &str, files: &SourceMap) -> String {
+    let number = span.start.line.to_string();
+    let gutter = " ".repeat(number.len());
+    let before = match source.get(..span.start.offset as usize) {
+        Some(x) => x,
+        None => return format!("{}--> {}", gutter, files.location(&span.start))
+    };
+    let line_start = before.rfind('\n').map(|x| x + 1).unwrap_or(0);
+    let line_end = source[line_start..].find('\n').map(|x| x + line_start).unwrap_or(source.len());
+    let line = source[line_start..line_end].trim_end_matches('\r');
+
+    /* Keep tabs in the padding so the caret lines up with the source line */
+    let padding : String = line.chars()
+        .take(span.start.column.saturating_sub(1) as usize)
+        .map(|x| if x == '\t' { '\t' } else { ' ' })
+        .collect();
+    let width = match span.end.line == span.start.line {
+        true => span.end.column.saturating_sub(span.start.column) as usize,
+        false => line.chars().count().saturating_sub(padding.chars().count())
+    };
+
+    format!("{}--> {}\n{} |\n{} | {}\n{} | {}{}",
+        gutter, files.location(&span.start),
+        gutter,
+        number, line,
+        gutter, padding, "^".repeat(width.max(1)))
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyntaxError::IllegalCharacter(_, ch) => write!(f, "Illegal character found in text: '{}'", ch),
+            SyntaxError::InvalidToken(_, text) => write!(f, "Invalid token: {}", text),
+            SyntaxError::InvalidNumber(_, text) => write!(f, "Invalid number: {}", text),
+            SyntaxError::DivideByZero(_, text) => write!(f, "Divide by zero in ratio: {}", text),
+            SyntaxError::RadixOutOfRange(_, text) => write!(f, "Radix out of range: {}", text),
+            SyntaxError::UnterminatedString(span) => write!(f, "Unterminated string literal starting at {}", span.start),
+            SyntaxError::UnterminatedRegex(span) => write!(f, "Unterminated regular expression starting at {}", span.start),
+            SyntaxError::UnsupportedEscape(_, ch) => write!(f, "Unsupported escape character: \\{}", ch),
+            SyntaxError::InvalidUnicodeEscape(_, text) => write!(f, "Invalid unicode escape: \\{}", text),
+            SyntaxError::InvalidOctalEscape(_, text) => write!(f, "Octal escape sequence must be in range [0, 377]: \\{}", text),
+            SyntaxError::InvalidOctalLength(_, text) => write!(f, "Invalid octal escape sequence length: \\{}", text),
+            SyntaxError::UnterminatedCharacter(_) => write!(f, "End of file while reading character literal!"),
+            SyntaxError::UnsupportedCharacter(_, text) => write!(f, "Unsupported character: \\{}", text),
+            SyntaxError::InvalidUnicodeCharacter(_, text) => write!(f, "Invalid unicode character: \\{}", text),
+            SyntaxError::InvalidCharacterConstant(_, text) => write!(f, "Invalid character constant: \\{}", text),
+            SyntaxError::MismatchedDelimiter(_, found, expected, _) => {
+                write!(f, "Closing '{}' without opening '{}', expected '{}'", found, opening_of(*found), expected)
+            },
+            SyntaxError::UnmatchedDelimiter(_, found) => write!(f, "Closing '{}' without opening parenthesis", found),
+            SyntaxError::UnknownSymbolicValue(_, text) => write!(f, "Unknown symbolic value: ##{}", text),
+            SyntaxError::UnexpectedToken(_, text) => write!(f, "Unexpected token: {}", text),
+            SyntaxError::UnexpectedEndOfFile(_) => write!(f, "Unexpected end of file!"),
+            SyntaxError::UnexpectedClosingDelimiter(_) => write!(f, "Unexpected closing delimiter!"),
+            SyntaxError::MissingClosingDelimiter(_, expected, _, _) => write!(f, "Missing closing '{}' at end of file!", expected),
+            SyntaxError::OddNumberOfMapForms(_) => write!(f, "Map literal must contain an even number of forms!"),
+            SyntaxError::DuplicateKey(_, key) => write!(f, "Duplicate key: {}", key),
+            SyntaxError::MissingDispatchForm(_) => write!(f, "Unexpected '#' without dispatch form!"),
+            SyntaxError::UnquoteSplicingNotInList(_) => write!(f, "Unquote splicing used outside of a list!"),
+            SyntaxError::NestedAnonymousFn(_) => write!(f, "Nested #()s are not allowed!"),
+            SyntaxError::InvalidArgLiteral(_, text) => write!(f, "Arg literal must be %, %& or %integer: {}", text),
+            SyntaxError::InvalidMetadata(_) => write!(f, "Metadata must be Symbol, Keyword, String, Map or Vector!"),
+            SyntaxError::InvalidMetadataTarget(_) => write!(f, "Metadata can only be applied to symbols and collections!"),
+            SyntaxError::ReaderConditionalNotList(_) => write!(f, "Reader conditional body must be a list!"),
+            SyntaxError::ReaderConditionalOddForms(_) => write!(f, "Reader conditional requires an even number of forms!"),
+            SyntaxError::FeatureNotKeyword(_) => write!(f, "Feature should be a keyword!"),
+            SyntaxError::SplicingNotInCollection(_) => write!(f, "Reader conditional splicing not allowed outside a collection!"),
+            SyntaxError::SplicedFormNotSequential(_) => write!(f, "Spliced form in reader conditional must be a list or vector!"),
+            SyntaxError::UnknownTag(_, tag) => write!(f, "No reader function for tag {}", tag),
+            SyntaxError::InvalidTaggedLiteral(_, text) => write!(f, "{}", text),
+            SyntaxError::InvalidDataReaders(_) => write!(f, "Data readers must be a map of namespaced tag symbols to reader symbols!")
+        }
+    }
+}
+
+impl std::error::Error for SyntaxError {}
+
+fn opening_of(closing: char) -> char {
+    match closing {
+        ')' => '(',
+        ']' => '[',
+        _ => '{'
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::source_position::{Position, SourceMap, Span};
+
+    use super::SyntaxError;
+
+    #[test]
+    fn render_with_caret() {
+        let source = "(def x 1)\n(+ 12abc 1)\n";
+        let error = SyntaxError::InvalidNumber(Span::new(Position::new(0, 2, 4, 13), Position::new(0, 2, 9, 18)), Box::new("12abc".to_string()));
+
+        assert_eq!(error.render(source, &SourceMap::new()), "error: Invalid number: 12abc\n --> 2:4\n  |\n2 | (+ 12abc 1)\n  |    ^^^^^")
+    }
+
+    #[test]
+    fn render_multi_line_span() {
+        let mut files = SourceMap::new();
+        let core = files.add_file("core.clj");
+        let source = "(a\n\t\"abc\r\ndef";
+        let error = SyntaxError::UnterminatedString(Span::new(Position::new(core, 2, 2, 4), Position::new(core, 3, 4, 13)));
+
+        assert_eq!(error.render(source, &files), "error: Unterminated string literal starting at 2:2\n --> core.clj:2:2\n  |\n2 | \t\"abc\n  | \t^^^^")
+    }
+
+    #[test]
+    fn render_delimiter_notes() {
+        let source = "(defn f [x]\n  (inc x)\n\n(def y 1)\n";
+        let error = SyntaxError::MissingClosingDelimiter(Span::new(Position::new(0, 5, 1, 33), Position::new(0, 5, 1, 33)), ')',
+            Span::new(Position::new(0, 1, 1, 0), Position::new(0, 1, 2, 1)), Some(Position::new(0, 2, 10, 21)));
+
+        assert_eq!(error.render(source, &SourceMap::new()), concat!(
+            "error: Missing closing ')' at end of file!\n --> 5:1\n  |\n5 | \n  | ^\n",
+            "note: unclosed '(' opened here\n --> 1:1\n  |\n1 | (defn f [x]\n  | ^\n",
+            "help: ')' is probably missing here, judging by the indentation\n --> 2:10\n  |\n2 |   (inc x)\n  |          ^"))
+    }
+
+    #[test]
+    fn render_outside_source() {
+        let error = SyntaxError::IllegalCharacter(Span::new(Position::new(0, 1, 2, 1), Position::new(0, 1, 3, 3)), '@');
+
+        assert_eq!(error.render("λx", &SourceMap::new()), "error: Illegal character found in text: '@'\n --> 1:2");
+        assert_eq!(error.render("", &SourceMap::new()), "error: Illegal character found in text: '@'\n --> 1:2")
+    }
+
+    #[test]
+    fn error_trait() {
+        let error : Box<dyn std::error::Error> = Box::new(SyntaxError::UnexpectedEndOfFile(Span::new(Position::default(), Position::default())));
+
+        assert_eq!(error.to_string(), "Unexpected end of file!")
+    }
+}
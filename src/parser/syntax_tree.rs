@@ -0,0 +1,383 @@
+use super::lexical_analyzer::{ LexicalAnalyzer, LexicalAnalyzerMethods, Symbols };
+use super::source_position::{ Position, Span };
+use super::syntax_error::SyntaxError;
+
+/* Source text between tokens, a discarded #_ form is kept whole together with the form it drops */
+#[derive(Clone, PartialEq, Debug)]
+pub enum Trivia {
+    Whitespace(Span, Box<String>),
+    Comment(Span, Box<String>),
+    Discard(Span, Box<String>)
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SyntaxKind {
+    Unit,
+    List,
+    Vector,
+    Map,
+    Set,
+    AnonymousFn,
+    VarQuote,
+    Quote,
+    SyntaxQuote,
+    Unquote,
+    UnquoteSplicing,
+    Meta,
+    Conditional,
+    Tagged
+}
+
+/* Lossless tree of the source, every token keeps its exact text and the trivia in front of it.
+ * Nothing is expanded, so printing the tree gives back the input byte for byte.
+ */
+#[derive(Clone, PartialEq, Debug)]
+pub enum SyntaxNode {
+    Token(Vec<Trivia>, Symbols, Box<String>),
+    Node(SyntaxKind, Vec<SyntaxNode>)
+}
+
+impl Trivia {
+    pub fn span(&self) -> Span {
+        match self {
+            Trivia::Whitespace(span, _) |
+            Trivia::Comment(span, _) |
+            Trivia::Discard(span, _) => *span
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        match self {
+            Trivia::Whitespace(_, text) |
+            Trivia::Comment(_, text) |
+            Trivia::Discard(_, text) => text
+        }
+    }
+}
+
+impl SyntaxNode {
+    /* The span of the tokens, leading trivia is not included */
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxNode::Token(_, symbol, _) => symbol.span(),
+            SyntaxNode::Node(_, children) => match (children.first(), children.last()) {
+                (Some(first), Some(last)) => first.span().merge(&last.span()),
+                _ => Span::new(Position::default(), Position::default())
+            }
+        }
+    }
+
+    /* The child nodes that are forms, leaving out delimiters and reader macro characters */
+    pub fn forms(&self) -> Vec<&SyntaxNode> {
+        match self {
+            SyntaxNode::Node(_, children) => children.iter().filter(|x| match x {
+                SyntaxNode::Token(_, symbol, _) => is_form(symbol),
+                _ => true
+            }).collect(),
+            _ => vec![]
+        }
+    }
+}
+
+pub trait SyntaxTreeParserMethods<'a> {
+    fn new(lexer: LexicalAnalyzer<'a>) -> Self;
+    fn advance(&mut self) -> Result<(), SyntaxError>;
+    fn take_token(&mut self) -> Result<SyntaxNode, SyntaxError>;
+    fn parse_start_unit(&mut self) -> Result<SyntaxNode, SyntaxError>;
+    fn parse_form(&mut self) -> Result<SyntaxNode, SyntaxError>;
+    fn parse_collection(&mut self, kind: SyntaxKind, children: Vec<SyntaxNode>) -> Result<SyntaxNode, SyntaxError>;
+    fn parse_dispatch(&mut self) -> Result<SyntaxNode, SyntaxError>;
+    fn parse_prefixed(&mut self, kind: SyntaxKind, children: Vec<SyntaxNode>, count: usize) -> Result<SyntaxNode, SyntaxError>;
+}
+
+/* Reads the same syntax as ExpressionParser into a SyntaxNode tree, for formatters, refactoring tools and editors */
+pub struct SyntaxTreeParser<'a> {
+    lexer: LexicalAnalyzer<'a>,
+    base: u32,
+    last_end: Position,
+    leading: Vec<Trivia>,
+    symbol: Symbols
+}
+
+impl<'a> SyntaxTreeParserMethods<'a> for SyntaxTreeParser<'a> {
+
+    fn new(lexer: LexicalAnalyzer<'a>) -> Self {
+        let start = lexer.position();
+        SyntaxTreeParser {
+            lexer,
+            base: start.offset,
+            last_end: start,
+            leading: Vec::new(),
+            symbol: Symbols::EOF(start, start)
+        }
+    }
+
+    /* Reads the next token and collects the text skipped in front of it as trivia, #_ and its form included */
+    fn advance(&mut self) -> Result<(), SyntaxError> {
+        let symbol = self.lexer.get_symbol()?;
+        let start = symbol.span().start;
+        let mut leading = split_trivia(self.text(self.last_end, start), self.last_end);
+        self.last_end = symbol.span().end;
+
+        if let Symbols::Discard(_, _) = symbol {
+            self.advance()?;
+            self.parse_form()?;
+            let end = self.last_end_of_form();
+            leading.push(Trivia::Discard(Span::new(start, end), Box::new(self.text(start, end).to_string())));
+            leading.append(&mut self.leading);
+            self.leading = leading;
+            return Ok(())
+        }
+
+        self.leading = leading;
+        self.symbol = symbol;
+        Ok(())
+    }
+
+    fn take_token(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let span = self.symbol.span();
+        let text = match self.symbol {
+            /* Text after a NUL character is never lexed, it is kept with the end of file */
+            Symbols::EOF(_, _) => &self.lexer.source()[(span.start.offset - self.base) as usize..],
+            _ => self.text(span.start, span.end)
+        }.to_string();
+        let token = SyntaxNode::Token(std::mem::take(&mut self.leading), self.symbol.clone(), Box::new(text));
+        if !matches!(self.symbol, Symbols::EOF(_, _)) {
+            self.advance()?
+        }
+        Ok(token)
+    }
+
+    fn parse_start_unit(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        self.advance()?;
+        let mut children : Vec<SyntaxNode> = Vec::new();
+        while !matches!(self.symbol, Symbols::EOF(_, _)) {
+            children.push(self.parse_form()?)
+        }
+        children.push(self.take_token()?);
+        Ok(SyntaxNode::Node(SyntaxKind::Unit, children))
+    }
+
+    fn parse_form(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        match self.symbol {
+            Symbols::LeftParen(_, _) => self.parse_collection(SyntaxKind::List, vec![]),
+            Symbols::LeftBracket(_, _) => self.parse_collection(SyntaxKind::Vector, vec![]),
+            Symbols::LeftCurly(_, _) => self.parse_collection(SyntaxKind::Map, vec![]),
+            Symbols::Hash(_, _) => self.parse_dispatch(),
+            Symbols::Quote(_, _) => self.parse_prefixed(SyntaxKind::Quote, vec![], 1),
+            Symbols::SyntaxQuote(_, _) => self.parse_prefixed(SyntaxKind::SyntaxQuote, vec![], 1),
+            Symbols::Unquote(_, _) => self.parse_prefixed(SyntaxKind::Unquote, vec![], 1),
+            Symbols::UnquoteSplicing(_, _) => self.parse_prefixed(SyntaxKind::UnquoteSplicing, vec![], 1),
+            Symbols::Caret(_, _) => self.parse_prefixed(SyntaxKind::Meta, vec![], 2),
+            Symbols::ReaderConditional(_, _) | Symbols::ReaderConditionalSplicing(_, _) => self.parse_prefixed(SyntaxKind::Conditional, vec![], 1),
+            Symbols::EOF(_, _) => Err(SyntaxError::UnexpectedEndOfFile(self.symbol.span())),
+            Symbols::RightParen(_, _) | Symbols::RightBracket(_, _) | Symbols::RightCurly(_, _) => {
+                Err(SyntaxError::UnexpectedClosingDelimiter(self.symbol.span()))
+            },
+            _ => self.take_token()
+        }
+    }
+
+    /* The opening delimiter is the current token, children holds the tokens read before it like the '#' of a set */
+    fn parse_collection(&mut self, kind: SyntaxKind, mut children: Vec<SyntaxNode>) -> Result<SyntaxNode, SyntaxError> {
+        children.push(self.take_token()?);
+        loop {
+            match self.symbol {
+                Symbols::RightParen(_, _) | Symbols::RightBracket(_, _) | Symbols::RightCurly(_, _) => {
+                    children.push(self.take_token()?);
+                    return Ok(SyntaxNode::Node(kind, children))
+                },
+                Symbols::EOF(_, _) => return Err(self.lexer.missing_closing_delimiter(self.symbol.span())),
+                _ => children.push(self.parse_form()?)
+            }
+        }
+    }
+
+    fn parse_dispatch(&mut self) -> Result<SyntaxNode, SyntaxError> {
+        let hash = self.symbol.span();
+        let children = vec![self.take_token()?];
+
+        match &self.symbol {
+            Symbols::LeftCurly(x, _) if *x == hash.end => self.parse_collection(SyntaxKind::Set, children),
+            Symbols::LeftParen(x, _) if *x == hash.end => self.parse_collection(SyntaxKind::AnonymousFn, children),
+            Symbols::Quote(x, _) if *x == hash.end => self.parse_prefixed(SyntaxKind::VarQuote, children, 1),
+            Symbols::LiteralName(x, _, _, _) if *x == hash.end => self.parse_prefixed(SyntaxKind::Tagged, children, 1),
+            _ => Err(SyntaxError::MissingDispatchForm(hash))
+        }
+    }
+
+    /* Reader macros, the current token followed by count forms */
+    fn parse_prefixed(&mut self, kind: SyntaxKind, mut children: Vec<SyntaxNode>, count: usize) -> Result<SyntaxNode, SyntaxError> {
+        children.push(self.take_token()?);
+        for _ in 0..count {
+            children.push(self.parse_form()?)
+        }
+        Ok(SyntaxNode::Node(kind, children))
+    }
+}
+
+impl<'a> SyntaxTreeParser<'a> {
+    fn text(&self, start: Position, end: Position) -> &str {
+        &self.lexer.source()[(start.offset - self.base) as usize..(end.offset - self.base) as usize]
+    }
+
+    /* The end of the form just read, which is where the trivia of the current token starts */
+    fn last_end_of_form(&self) -> Position {
+        match self.leading.first() {
+            Some(trivia) => trivia.span().start,
+            None => self.symbol.span().start
+        }
+    }
+}
+
+/* Splits the text skipped by the lexer into whitespace runs and comments, commas count as whitespace */
+fn split_trivia(text: &str, start: Position) -> Vec<Trivia> {
+    let mut trivia : Vec<Trivia> = Vec::new();
+    let mut rest = text;
+    let mut position = start;
+
+    while !rest.is_empty() {
+        let is_comment = rest.starts_with(';') || rest.starts_with("#!");
+        let length = match is_comment {
+            true => rest.find(['\r', '\n']).unwrap_or(rest.len()),
+            false => rest.find(';').into_iter().chain(rest.find("#!")).min().unwrap_or(rest.len())
+        };
+        let (piece, tail) = rest.split_at(length);
+        let end = advance_position(position, piece);
+        trivia.push(match is_comment {
+            true => Trivia::Comment(Span::new(position, end), Box::new(piece.to_string())),
+            false => Trivia::Whitespace(Span::new(position, end), Box::new(piece.to_string()))
+        });
+        rest = tail;
+        position = end
+    }
+    trivia
+}
+
+/* Moves the position past the text, counting lines and columns the same way as the lexer */
+fn advance_position(mut position: Position, text: &str) -> Position {
+    for ch in text.chars() {
+        position.offset += ch.len_utf8() as u32;
+        match ch {
+            '\n' => {
+                position.line += 1;
+                position.column = 1
+            },
+            _ => position.column += 1
+        }
+    }
+    position
+}
+
+/* Delimiters and reader macro characters are part of the syntax around a form */
+fn is_form(symbol: &Symbols) -> bool {
+    !matches!(symbol,
+        Symbols::EOF(_, _) | Symbols::LeftParen(_, _) | Symbols::RightParen(_, _) | Symbols::LeftBracket(_, _) |
+        Symbols::RightBracket(_, _) | Symbols::LeftCurly(_, _) | Symbols::RightCurly(_, _) | Symbols::Hash(_, _) |
+        Symbols::Quote(_, _) | Symbols::SyntaxQuote(_, _) | Symbols::Unquote(_, _) | Symbols::UnquoteSplicing(_, _) |
+        Symbols::Discard(_, _) | Symbols::Caret(_, _) | Symbols::ReaderConditional(_, _) | Symbols::ReaderConditionalSplicing(_, _))
+}
+
+/* Prints the exact source text the tree was read from */
+impl std::fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SyntaxNode::Token(leading, _, text) => {
+                for trivia in leading {
+                    write!(f, "{}", trivia.text())?
+                }
+                write!(f, "{}", text)
+            },
+            SyntaxNode::Node(_, children) => {
+                for child in children {
+                    write!(f, "{}", child)?
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+
+///////////////////////////////////////////////////////////////////////////////
+// Unittests below
+///////////////////////////////////////////////////////////////////////////////
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::lexical_analyzer::{LexicalAnalyzer, LexicalAnalyzerMethods, Symbols};
+    use crate::parser::source_position::{Position, Span};
+    use crate::parser::syntax_error::SyntaxError;
+
+    use super::{SyntaxKind, SyntaxNode, SyntaxTreeParser, SyntaxTreeParserMethods, Trivia};
+
+    fn parse(text: &str) -> Result<SyntaxNode, SyntaxError> {
+        SyntaxTreeParser::new(LexicalAnalyzer::new(text)).parse_start_unit()
+    }
+
+    #[test]
+    fn round_trip() {
+        let sources = [
+            "",
+            "  \n",
+            "#!/usr/bin/env clj\n(ns app.core\n  (:require [clojure.string :as str]))\n\n;; Entry point\n(defn -main [& args]\n  (println \"hi\"))  ; done\n",
+            "{:a 1, :b 2 ,, :c #_ #_ x y 3}\r\n",
+            "`(let [x# ~a] ~@body) '#{1 2} #'foo #(+ % %2)",
+            "^:private ^{:doc \"d\"} (def x #\"[a-z]+\") #inst \"2024-01-01\" #?(:clj 1 :cljs 2) [#?@(:cljs [a])]",
+            "(\\a \\newline 1/2 -1.5e3 42N 1.0M ##Inf nil true) #_(unread [stuff]) ; trailing",
+            "#_ (a) ;; only discarded"
+        ];
+
+        for source in sources {
+            match parse(source) {
+                Ok(x) => assert_eq!(x.to_string(), source),
+                x => panic!("Unexpected result {:?} for {:?}", x, source)
+            }
+        }
+    }
+
+    #[test]
+    fn trivia_is_attached_to_the_next_token() {
+        let p = |offset: u32| Position::new(0, 1, offset + 1, offset);
+        let unit = parse("[a ;c\n #_ b, c]").unwrap();
+
+        let vector = match &unit {
+            SyntaxNode::Node(SyntaxKind::Unit, children) => &children[0],
+            x => panic!("Unexpected node {:?}", x)
+        };
+        match vector {
+            SyntaxNode::Node(SyntaxKind::Vector, children) => {
+                assert_eq!(vector.forms().len(), 2);
+                assert_eq!(children[2], SyntaxNode::Token(vec![
+                    Trivia::Whitespace(Span::new(p(2), p(3)), Box::new(" ".to_string())),
+                    Trivia::Comment(Span::new(p(3), p(5)), Box::new(";c".to_string())),
+                    Trivia::Whitespace(Span::new(p(5), Position::new(0, 2, 2, 7)), Box::new("\n ".to_string())),
+                    Trivia::Discard(Span::new(Position::new(0, 2, 2, 7), Position::new(0, 2, 6, 11)), Box::new("#_ b".to_string())),
+                    Trivia::Whitespace(Span::new(Position::new(0, 2, 6, 11), Position::new(0, 2, 8, 13)), Box::new(", ".to_string()))
+                ], Symbols::LiteralName(Position::new(0, 2, 8, 13), Position::new(0, 2, 9, 14), None, Box::new("c".to_string())), Box::new("c".to_string())))
+            },
+            x => panic!("Unexpected node {:?}", x)
+        }
+    }
+
+    #[test]
+    fn reader_macros_keep_their_structure() {
+        let unit = parse("'a ^:k b #{c} #tag d").unwrap();
+        let kinds : Vec<SyntaxKind> = unit.forms().iter().filter_map(|x| match x {
+            SyntaxNode::Node(kind, _) => Some(*kind),
+            _ => None
+        }).collect();
+
+        assert_eq!(kinds, vec![SyntaxKind::Quote, SyntaxKind::Meta, SyntaxKind::Set, SyntaxKind::Tagged])
+    }
+
+    #[test]
+    fn syntax_errors() {
+        let p = |offset: u32| Position::new(0, 1, offset + 1, offset);
+
+        assert_eq!(parse("(a"), Err(SyntaxError::MissingClosingDelimiter(Span::new(p(2), p(2)), ')', Span::new(p(0), p(1)), None)));
+        assert_eq!(parse("'"), Err(SyntaxError::UnexpectedEndOfFile(Span::new(p(1), p(1)))));
+        assert_eq!(parse("# a"), Err(SyntaxError::MissingDispatchForm(Span::new(p(0), p(1)))))
+    }
+}